@@ -0,0 +1,383 @@
+//! `#[derive(FromRequest)]`, re-exported by `http-util` with the `derive` feature
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, GenericArgument, Ident, LitStr,
+    PathArguments, Type,
+};
+
+enum Source {
+    /// Next variable of `PathVariable` which is the parameter
+    Path,
+    /// Whole query string
+    Query,
+    Cookie(LitStr),
+    Header(LitStr),
+    Body,
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    source: Source,
+}
+
+/// Implements `FromRequest<'a>` and generates `{Name}Error` combining errors of the fields
+///
+/// - `#[path_variable]`: next variable of `PathVariable`, which becomes the `Parameter`.
+///   `#[path]` is taken by the built-in attribute for modules
+/// - `#[query]`: deserializes the whole query string with `Query`
+/// - `#[cookie("name")]`, `#[header("x-user-id")]`: parsed with `FromStr`, `Option<T>` if optional
+/// - `#[body]`: `BodyParser`, read after the other fields
+#[proc_macro_derive(FromRequest, attributes(path_variable, query, cookie, header, body))]
+pub fn derive_from_request(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "FromRequest can not be derived for generic types",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "FromRequest can be derived only for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "FromRequest can be derived only for structs",
+            ))
+        }
+    };
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            Ok(Field {
+                ident: field.ident.clone().unwrap(),
+                ty: field.ty.clone(),
+                source: source(field)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let has = |f: fn(&Source) -> bool| fields.iter().any(|x| f(&x.source));
+    let has_path = has(|x| matches!(x, Source::Path));
+    let has_query = has(|x| matches!(x, Source::Query));
+    let has_cookie = has(|x| matches!(x, Source::Cookie(_)));
+    let has_header = has(|x| matches!(x, Source::Header(_)));
+    let has_body = has(|x| matches!(x, Source::Body));
+
+    if fields
+        .iter()
+        .filter(|x| matches!(x.source, Source::Body))
+        .count()
+        > 1
+    {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "only one field can be #[body]",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let error = format_ident!("{}Error", name);
+    let private = quote!(::http_util::__private);
+
+    let error_enum = error_enum(
+        vis, &error, has_path, has_query, has_cookie, has_header, has_body,
+    );
+
+    let parameter = if has_path {
+        quote!(::http_util::PathVariable)
+    } else {
+        quote!(())
+    };
+
+    let cookie = has_cookie.then(|| quote!(let __cookie = ::http_util::Cookie::from(&*request);));
+
+    // the body is read at last
+    let extracts = fields
+        .iter()
+        .filter(|x| !matches!(x.source, Source::Body))
+        .chain(fields.iter().filter(|x| matches!(x.source, Source::Body)))
+        .map(|field| extract(field, &error));
+
+    let idents = fields.iter().map(|x| &x.ident);
+
+    Ok(quote! {
+        #error_enum
+
+        #[#private::async_trait]
+        impl<'a> ::http_util::FromRequest<'a> for #name {
+            type Parameter = #parameter;
+            type Error = #error;
+
+            #[allow(unused_variables, unused_mut)]
+            async fn from_request(
+                param: Self::Parameter,
+                request: &'a mut #private::http::Request<#private::hyper::Body>,
+            ) -> ::std::result::Result<Self, Self::Error> {
+                let mut __path = param;
+                #cookie
+
+                #(#extracts)*
+
+                ::std::result::Result::Ok(Self { #(#idents),* })
+            }
+        }
+    })
+}
+
+fn source(field: &syn::Field) -> syn::Result<Source> {
+    let mut sources = Vec::new();
+
+    for attr in &field.attrs {
+        let path = attr.path();
+
+        if path.is_ident("path_variable") {
+            attr.meta.require_path_only()?;
+            sources.push(Source::Path);
+        } else if path.is_ident("query") {
+            attr.meta.require_path_only()?;
+            sources.push(Source::Query);
+        } else if path.is_ident("cookie") {
+            sources.push(Source::Cookie(attr.parse_args()?));
+        } else if path.is_ident("header") {
+            sources.push(Source::Header(attr.parse_args()?));
+        } else if path.is_ident("body") {
+            attr.meta.require_path_only()?;
+            sources.push(Source::Body);
+        }
+    }
+
+    match sources.len() {
+        1 => Ok(sources.pop().unwrap()),
+        0 => Err(syn::Error::new(
+            field.span(),
+            "expected one of #[path_variable], #[query], #[cookie(\"name\")], #[header(\"name\")] or #[body]",
+        )),
+        _ => Err(syn::Error::new(
+            field.span(),
+            "a field can have only one source attribute",
+        )),
+    }
+}
+
+/// `T` of `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last()?,
+        _ => return None,
+    };
+
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn extract(field: &Field, error: &Ident) -> TokenStream2 {
+    let Field { ident, ty, source } = field;
+    let private = quote!(::http_util::__private);
+
+    match source {
+        Source::Path => quote! {
+            let #ident = __path
+                .try_next_variable::<#ty>()
+                .map_err(#error::Path)?;
+        },
+        Source::Query => quote! {
+            let #ident = <::http_util::Query<#ty>>::from_query(
+                request.uri().query().unwrap_or_default(),
+            )
+            .map_err(#error::Query)?
+            .into_inner();
+        },
+        Source::Cookie(name) | Source::Header(name) => {
+            let (value, missing, invalid) = match source {
+                Source::Cookie(_) => (
+                    quote!(__cookie.get(#name)),
+                    quote!(MissingCookie),
+                    quote!(InvalidCookie),
+                ),
+                _ => (
+                    quote! {
+                        match request.headers().get(#name).map(|x| x.to_str()) {
+                            ::std::option::Option::Some(::std::result::Result::Ok(x)) => {
+                                ::std::option::Option::Some(x)
+                            }
+                            ::std::option::Option::Some(::std::result::Result::Err(e)) => {
+                                return ::std::result::Result::Err(#error::InvalidHeader {
+                                    name: #name,
+                                    message: e.to_string(),
+                                });
+                            }
+                            ::std::option::Option::None => ::std::option::Option::None,
+                        }
+                    },
+                    quote!(MissingHeader),
+                    quote!(InvalidHeader),
+                ),
+            };
+
+            let (inner, required) = match option_inner(ty) {
+                Some(inner) => (inner, None),
+                None => (ty, Some(quote!(.ok_or(#error::#missing(#name))?))),
+            };
+
+            quote! {
+                let #ident = match #value {
+                    ::std::option::Option::Some(x) => ::std::option::Option::Some(
+                        x.parse::<#inner>().map_err(|e| #error::#invalid {
+                            name: #name,
+                            message: e.to_string(),
+                        })?,
+                    ),
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+                #required;
+            }
+        }
+        Source::Body => quote! {
+            let #ident = <#private::http::Request<#private::hyper::Body>
+                as ::http_util::BodyParser<#ty>>::body_parse(request)
+                .await
+                .map_err(#error::Body)?;
+        },
+    }
+}
+
+fn error_enum(
+    vis: &syn::Visibility,
+    error: &Ident,
+    has_path: bool,
+    has_query: bool,
+    has_cookie: bool,
+    has_header: bool,
+    has_body: bool,
+) -> TokenStream2 {
+    let private = quote!(::http_util::__private);
+
+    let mut variants = Vec::new();
+    let mut displays = Vec::new();
+    let mut sources = Vec::new();
+    let mut status_codes = Vec::new();
+
+    if has_path {
+        variants.push(quote!(Path(::http_util::PathVariableError)));
+        displays.push(quote!(Self::Path(e) => ::std::write!(f, "Path: {}", e)));
+        sources.push(quote!(Self::Path(e) => ::std::option::Option::Some(e)));
+        status_codes.push(quote! {
+            Self::Path(::http_util::PathVariableError::Missing(_)) => #private::http::StatusCode::NOT_FOUND
+        });
+    }
+
+    if has_query {
+        variants.push(quote!(Query(::http_util::QueryError)));
+        displays.push(quote!(Self::Query(e) => ::std::write!(f, "Query: {}", e)));
+        sources.push(quote!(Self::Query(e) => ::std::option::Option::Some(e)));
+    }
+
+    if has_cookie {
+        variants.push(quote!(MissingCookie(&'static str)));
+        variants.push(quote!(InvalidCookie {
+            name: &'static str,
+            message: ::std::string::String
+        }));
+        displays.push(
+            quote!(Self::MissingCookie(name) => ::std::write!(f, "Missing cookie: {}", name)),
+        );
+        displays.push(quote! {
+            Self::InvalidCookie { name, message } => ::std::write!(f, "Invalid cookie {}: {}", name, message)
+        });
+    }
+
+    if has_header {
+        variants.push(quote!(MissingHeader(&'static str)));
+        variants.push(quote!(InvalidHeader {
+            name: &'static str,
+            message: ::std::string::String
+        }));
+        displays.push(
+            quote!(Self::MissingHeader(name) => ::std::write!(f, "Missing header: {}", name)),
+        );
+        displays.push(quote! {
+            Self::InvalidHeader { name, message } => ::std::write!(f, "Invalid header {}: {}", name, message)
+        });
+    }
+
+    if has_body {
+        variants.push(quote!(Body(::http_util::body_parser::Error)));
+        displays.push(quote!(Self::Body(e) => ::std::write!(f, "Body: {}", e)));
+        sources.push(quote!(Self::Body(e) => ::std::option::Option::Some(e)));
+        status_codes.push(quote!(Self::Body(e) => e.status_code()));
+    }
+
+    // an enum without variants can not be matched with a wildcard arm
+    let (display, source, status_code) = if variants.is_empty() {
+        (
+            quote!(match *self {}),
+            quote!(match *self {}),
+            quote!(match *self {}),
+        )
+    } else {
+        (
+            quote!(match self { #(#displays,)* }),
+            quote!(match self { #(#sources,)* _ => ::std::option::Option::None }),
+            quote!(match self { #(#status_codes,)* _ => #private::http::StatusCode::BAD_REQUEST }),
+        )
+    };
+
+    quote! {
+        #[derive(Debug)]
+        #vis enum #error {
+            #(#variants,)*
+        }
+
+        impl #error {
+            /// 404 for a missing path variable, status code of the body error, or 400
+            #[allow(unreachable_patterns)]
+            pub fn status_code(&self) -> #private::http::StatusCode {
+                #status_code
+            }
+        }
+
+        impl ::std::fmt::Display for #error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                #display
+            }
+        }
+
+        impl ::std::error::Error for #error {
+            #[allow(unreachable_patterns)]
+            fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                #source
+            }
+        }
+    }
+}
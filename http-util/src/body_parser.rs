@@ -1,16 +1,152 @@
-use http::{header, Request};
+use std::{collections::HashMap, sync::Arc};
+
+use http::{header, Request, StatusCode};
 use hyper::Body;
-use serde::de::DeserializeOwned;
+use serde::de::{value::StrDeserializer, DeserializeOwned};
+
+use crate::{read_chunks, BodyLimits, ReadChunks};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
-use crate::ReadChunks;
+type Decode = dyn Fn(&[u8]) -> Result<serde_json::Value, BoxError> + Send + Sync;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Not supported content-type: {0}")]
     NotSupportedContentType(String),
 
+    #[error("Body: {0}")]
+    Body(hyper::Error),
+
     #[error("Json deserialize: {0}")]
-    JsonDeserialize(serde_json::Error),
+    JsonDeserialize(JsonError),
+
+    #[error("Form deserialize: {0}")]
+    FormDeserialize(serde_urlencoded::de::Error),
+
+    #[error("Text deserialize: {0}")]
+    TextDeserialize(serde::de::value::Error),
+
+    #[error("Not utf-8 text")]
+    NotUtf8,
+
+    #[cfg(feature = "msgpack")]
+    #[error("MessagePack deserialize: {0}")]
+    MsgpackDeserialize(rmp_serde::decode::Error),
+
+    #[cfg(feature = "cbor")]
+    #[error("CBOR deserialize: {0}")]
+    CborDeserialize(ciborium::de::Error<std::io::Error>),
+
+    #[error("Decode: {0}")]
+    Decode(BoxError),
+
+    #[error("Payload too large: more than {0} bytes")]
+    PayloadTooLarge(usize),
+
+    #[error("Timeout")]
+    Timeout,
+}
+
+/// `serde_json::Error` with the path of the failing field
+#[derive(Debug, thiserror::Error)]
+#[error("{path}: {inner}")]
+pub struct JsonError {
+    path: String,
+    #[source]
+    inner: serde_json::Error,
+}
+
+impl JsonError {
+    /// `.`, `user.name`, `items[0].id`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// 1-based, 0 if the error is not from the body text
+    pub fn line(&self) -> usize {
+        self.inner.line()
+    }
+
+    /// 1-based, 0 if the error is not from the body text
+    pub fn column(&self) -> usize {
+        self.inner.column()
+    }
+
+    pub fn inner(&self) -> &serde_json::Error {
+        &self.inner
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for JsonError {
+    fn from(err: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        Self {
+            path: err.path().to_string(),
+            inner: err.into_inner(),
+        }
+    }
+}
+
+impl Error {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::NotSupportedContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Timeout => StatusCode::REQUEST_TIMEOUT,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Decoders for custom media types
+///
+/// Decoders registered here take precedence over built-in decoders.
+/// Insert into extensions of the request to use with [`BodyParser::body_parse`].
+///
+/// ```no_run
+/// # use http_util::BodyDecoders;
+/// # mod serde_yaml {
+/// #     pub fn from_slice(_: &[u8]) -> serde_json::Result<serde_json::Value> {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// # fn handle(mut request: hyper::Request<hyper::Body>) {
+/// let decoders = BodyDecoders::new().register("application/yaml", |buf| {
+///     Ok(serde_yaml::from_slice(buf)?)
+/// });
+///
+/// request.extensions_mut().insert(decoders);
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct BodyDecoders {
+    inner: HashMap<String, Arc<Decode>>,
+}
+
+impl BodyDecoders {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn register<F>(mut self, media_type: &str, decode: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<serde_json::Value, BoxError> + Send + Sync + 'static,
+    {
+        self.inner
+            .insert(media_type.to_ascii_lowercase(), Arc::new(decode));
+
+        self
+    }
+
+    pub fn get(&self, media_type: &str) -> Option<&Decode> {
+        self.inner.get(media_type).map(|x| x.as_ref())
+    }
+}
+
+impl std::fmt::Debug for BodyDecoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.inner.keys()).finish()
+    }
 }
 
 #[async_trait::async_trait]
@@ -21,7 +157,18 @@ where
 {
     type Error;
 
+    /// Uses [`BodyDecoders`] and [`BodyLimits`] in extensions of the request, if any
     async fn body_parse(&mut self) -> Result<P, Self::Error>;
+
+    /// The default ignores the decoders and falls back to [`BodyParser::body_parse`]
+    async fn body_parse_with(&mut self, decoders: &BodyDecoders) -> Result<P, Self::Error>
+    where
+        Self: Send,
+    {
+        let _ = decoders;
+
+        self.body_parse().await
+    }
 }
 
 #[async_trait::async_trait]
@@ -33,24 +180,297 @@ where
     type Error = Error;
 
     async fn body_parse(&mut self) -> Result<P, Self::Error> {
-        let chunks = self.body_mut().read_chunks().await.unwrap_or_default();
+        let decoders = self
+            .extensions()
+            .get::<BodyDecoders>()
+            .cloned()
+            .unwrap_or_default();
+
+        self.body_parse_with(&decoders).await
+    }
+
+    async fn body_parse_with(&mut self, decoders: &BodyDecoders) -> Result<P, Self::Error> {
+        let limits = self
+            .extensions()
+            .get::<BodyLimits>()
+            .cloned()
+            .unwrap_or_default();
+
+        let chunks = match self.body_mut().read_chunks_with(&limits).await {
+            Ok(chunks) => chunks,
+            Err(read_chunks::Error::PayloadTooLarge(max_bytes)) => {
+                return Err(Error::PayloadTooLarge(max_bytes))
+            }
+            Err(read_chunks::Error::Timeout) => return Err(Error::Timeout),
+            Err(read_chunks::Error::Body(err)) => return Err(Error::Body(err)),
+        };
 
         let content_type = self
             .headers()
             .get(header::CONTENT_TYPE)
-            .map(|x| x.to_str().unwrap_or_default());
+            .map(|x| x.to_str().unwrap_or_default())
+            .unwrap_or_default();
+
+        decode(content_type, &chunks, decoders)
+    }
+}
+
+fn decode<P>(content_type: &str, chunks: &[u8], decoders: &BodyDecoders) -> Result<P, Error>
+where
+    P: DeserializeOwned,
+{
+    // application/json; charset=utf-8
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    if let Some(decode) = decoders.get(&media_type) {
+        let value = decode(chunks).map_err(Error::Decode)?;
+
+        return serde_path_to_error::deserialize(value)
+            .map_err(|err| Error::JsonDeserialize(err.into()));
+    }
+
+    match media_type.as_str() {
+        "application/json" => from_json_slice(chunks),
+        x if x.starts_with("application/") && x.ends_with("+json") => from_json_slice(chunks),
+        "application/x-www-form-urlencoded" => {
+            serde_urlencoded::from_bytes(chunks).map_err(Error::FormDeserialize)
+        }
+        "text/plain" => {
+            let text = std::str::from_utf8(chunks).map_err(|_| Error::NotUtf8)?;
+
+            P::deserialize(StrDeserializer::new(text)).map_err(Error::TextDeserialize)
+        }
+        #[cfg(feature = "msgpack")]
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+            rmp_serde::from_slice(chunks).map_err(Error::MsgpackDeserialize)
+        }
+        #[cfg(feature = "cbor")]
+        "application/cbor" => ciborium::de::from_reader(chunks).map_err(Error::CborDeserialize),
+        _ => Err(Error::NotSupportedContentType(content_type.to_owned())),
+    }
+}
+
+fn from_json_slice<P>(chunks: &[u8]) -> Result<P, Error>
+where
+    P: DeserializeOwned,
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(chunks);
+
+    let payload = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|err| Error::JsonDeserialize(err.into()))?;
+
+    deserializer.end().map_err(|inner| {
+        Error::JsonDeserialize(JsonError {
+            path: ".".to_owned(),
+            inner,
+        })
+    })?;
+
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use http::{header, Request, StatusCode};
+    use serde::Deserialize;
+
+    use super::{BodyDecoders, BodyParser, Error};
+    use crate::BodyLimits;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Payload {
+        name: String,
+        age: u8,
+    }
+
+    fn request(content_type: &str, body: impl Into<hyper::Body>) -> Request<hyper::Body> {
+        Request::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body.into())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_body_parse() {
+        let expected = Payload {
+            name: "syr".to_owned(),
+            age: 20,
+        };
+
+        let payload: Payload = request(
+            "application/json; charset=utf-8",
+            r#"{"name":"syr","age":20}"#,
+        )
+        .body_parse()
+        .await
+        .unwrap();
+
+        assert_eq!(payload, expected);
+
+        let payload: Payload = request("application/x-www-form-urlencoded", "name=syr&age=20")
+            .body_parse()
+            .await
+            .unwrap();
+
+        assert_eq!(payload, expected);
+
+        let payload: String = request("text/plain; charset=utf-8", "hello")
+            .body_parse()
+            .await
+            .unwrap();
+
+        assert_eq!(payload, "hello");
+
+        let r: Result<Payload, _> = request("application/xml", "<a />").body_parse().await;
+
+        assert!(matches!(r, Err(Error::NotSupportedContentType(x)) if x == "application/xml"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_body_parse_msgpack() {
+        let body =
+            rmp_serde::to_vec_named(&serde_json::json!({ "name": "syr", "age": 20 })).unwrap();
+
+        let payload: Payload = request("application/msgpack", body)
+            .body_parse()
+            .await
+            .unwrap();
+
+        assert_eq!(payload.age, 20);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_body_parse_cbor() {
+        let mut body = Vec::new();
+        ciborium::ser::into_writer(&serde_json::json!({ "name": "syr", "age": 20 }), &mut body)
+            .unwrap();
+
+        let payload: Payload = request("application/cbor", body)
+            .body_parse()
+            .await
+            .unwrap();
+
+        assert_eq!(payload.age, 20);
+    }
+
+    #[tokio::test]
+    async fn test_body_parse_json_error() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Team {
+            members: Vec<Payload>,
+        }
+
+        let body = "{\n  \"members\": [\n    { \"name\": \"a\", \"age\": 1 },\n    { \"name\": \"b\", \"age\": \"2\" }\n  ]\n}";
+
+        let r: Result<Team, _> = request("application/json", body).body_parse().await;
+
+        match r {
+            Err(Error::JsonDeserialize(err)) => {
+                assert_eq!(err.path(), "members[1].age");
+                assert_eq!(err.line(), 4);
+                assert_eq!(err.column(), 29);
+            }
+            r => panic!("{r:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_body_parse_body_error() {
+        let (mut sender, body) = hyper::Body::channel();
+
+        sender.send_data("{\"name\"".into()).await.unwrap();
+        sender.abort();
+
+        let r: Result<Payload, _> = request("application/json", body).body_parse().await;
+
+        assert!(matches!(r, Err(Error::Body(_))), "{r:?}");
+    }
+
+    #[tokio::test]
+    async fn test_body_parse_limits() {
+        let mut request = request("application/json", r#"{"name":"syr","age":20}"#);
+        request
+            .extensions_mut()
+            .insert(BodyLimits::new().max_bytes(10));
+
+        let r: Result<Payload, _> = request.body_parse().await;
+
+        let err = r.unwrap_err();
 
-        match content_type {
-            Some(content_type) if content_type.starts_with("application/json") => {
-                let payload =
-                    serde_json::from_slice::<P>(&chunks).map_err(Error::JsonDeserialize)?;
+        assert!(matches!(err, Error::PayloadTooLarge(10)));
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_body_parse_custom_decoder() {
+        // name:syr,age:20
+        let decoders = BodyDecoders::new().register("application/x-pairs", |buf| {
+            let st = std::str::from_utf8(buf)?;
+
+            let mut map = HashMap::<String, serde_json::Value>::new();
+
+            for pair in st.split(',') {
+                let (key, value) = pair.split_once(':').ok_or("invalid pair")?;
+
+                let value = match value.parse::<u64>() {
+                    Ok(x) => x.into(),
+                    Err(_) => value.into(),
+                };
 
-                Ok(payload)
+                map.insert(key.to_owned(), value);
             }
-            content_type => {
-                let content_type = content_type.unwrap_or_default().to_owned();
-                Err(Error::NotSupportedContentType(content_type))
+
+            Ok(serde_json::to_value(map)?)
+        });
+
+        let mut request = request("application/x-pairs", "name:syr,age:20");
+        request.extensions_mut().insert(decoders);
+
+        let payload: Payload = request.body_parse().await.unwrap();
+
+        assert_eq!(
+            payload,
+            Payload {
+                name: "syr".to_owned(),
+                age: 20
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_parse_with_default() {
+        struct Json(&'static str);
+
+        #[async_trait::async_trait]
+        impl BodyParser<Payload> for Json {
+            type Error = serde_json::Error;
+
+            async fn body_parse(&mut self) -> Result<Payload, Self::Error> {
+                serde_json::from_str(self.0)
             }
         }
+
+        let payload = Json(r#"{"name":"syr","age":20}"#)
+            .body_parse_with(&BodyDecoders::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            payload,
+            Payload {
+                name: "syr".to_owned(),
+                age: 20
+            }
+        );
     }
 }
@@ -0,0 +1,427 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use http::{Method, Request, StatusCode};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    body_parser::BoxError,
+    headers::{
+        ETag, EntityTag, Header, IfMatch, IfModifiedSince, IfNoneMatch, IfUnmodifiedSince,
+        LastModified,
+    },
+    SetResponse,
+};
+
+/// Strong `ETag` of the body, base64url of SHA-256
+pub fn strong_etag(body: &[u8]) -> EntityTag {
+    EntityTag::strong(URL_SAFE_NO_PAD.encode(Sha256::digest(body)))
+}
+
+/// Result of the preconditions of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+    /// 200, respond as usual
+    Passed,
+    /// 304
+    NotModified,
+    /// 412
+    Failed,
+}
+
+impl Precondition {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Passed => StatusCode::OK,
+            Self::NotModified => StatusCode::NOT_MODIFIED,
+            Self::Failed => StatusCode::PRECONDITION_FAILED,
+        }
+    }
+}
+
+/// `ETag` and `Last-Modified` of the selected representation
+///
+/// ```no_run
+/// # use chrono::{DateTime, Utc};
+/// # use http::{Request, Response};
+/// # use hyper::Body;
+/// # use http_util::{BoxError, Validators};
+/// # #[derive(serde::Serialize)]
+/// # struct Catalog {
+/// #     #[serde(skip)]
+/// #     updated_at: DateTime<Utc>,
+/// # }
+/// # fn handle(request: Request<Body>, catalog: Catalog) -> Result<(), BoxError> {
+/// # let mut response = Response::new(Body::empty());
+/// let body = serde_json::to_vec(&catalog)?;
+///
+/// Validators::from_body(&body)
+///     .last_modified(catalog.updated_at)
+///     .set_response(&request, &mut response, body)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<EntityTag>,
+    pub last_modified: Option<DateTime<Utc>>,
+    /// Whether the representation exists, `*` of `If-Match` and `If-None-Match` matches it
+    /// with or without `ETag`
+    pub exists: bool,
+}
+
+impl Default for Validators {
+    fn default() -> Self {
+        Self {
+            etag: None,
+            last_modified: None,
+            exists: true,
+        }
+    }
+}
+
+impl Validators {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// With [`strong_etag`] of the body
+    pub fn from_body(body: &[u8]) -> Self {
+        Self::new().etag(strong_etag(body))
+    }
+
+    pub fn etag(mut self, etag: EntityTag) -> Self {
+        self.etag.replace(etag);
+
+        self
+    }
+
+    pub fn last_modified(mut self, last_modified: DateTime<Utc>) -> Self {
+        self.last_modified.replace(last_modified);
+
+        self
+    }
+
+    /// `false` for a resource not created yet, such as the target of `PUT` with
+    /// `If-None-Match: *`
+    pub fn exists(mut self, exists: bool) -> Self {
+        self.exists = exists;
+
+        self
+    }
+
+    /// Evaluates the preconditions in the order of RFC 9110 13.2.2
+    ///
+    /// 1. `If-Match`, otherwise `If-Unmodified-Since`, fails with 412
+    /// 2. `If-None-Match`, otherwise `If-Modified-Since` of `GET` and `HEAD`, answers 304 for
+    ///    `GET` and `HEAD` and fails with 412 for the other methods
+    ///
+    /// Invalid headers and dates without `Last-Modified` are ignored.
+    pub fn evaluate<B>(&self, request: &Request<B>) -> Precondition {
+        let headers = request.headers();
+        let is_get_or_head = matches!(*request.method(), Method::GET | Method::HEAD);
+
+        if let Ok(if_match) = IfMatch::from_headers(headers) {
+            let matched = match (&if_match, &self.etag) {
+                (IfMatch::Any, _) => self.exists,
+                (_, Some(etag)) => self.exists && if_match.matches(etag),
+                (_, None) => false,
+            };
+
+            if !matched {
+                return Precondition::Failed;
+            }
+        } else if let (Ok(IfUnmodifiedSince(since)), Some(last_modified)) =
+            (IfUnmodifiedSince::from_headers(headers), self.last_modified)
+        {
+            if last_modified.timestamp() > since.timestamp() {
+                return Precondition::Failed;
+            }
+        }
+
+        if let Ok(if_none_match) = IfNoneMatch::from_headers(headers) {
+            let matched = match (&if_none_match, &self.etag) {
+                (IfNoneMatch::Any, _) => self.exists,
+                (_, Some(etag)) => self.exists && if_none_match.matches(etag),
+                (_, None) => false,
+            };
+
+            if matched {
+                return match is_get_or_head {
+                    true => Precondition::NotModified,
+                    false => Precondition::Failed,
+                };
+            }
+        } else if let (true, Ok(IfModifiedSince(since)), Some(last_modified)) = (
+            is_get_or_head,
+            IfModifiedSince::from_headers(headers),
+            self.last_modified,
+        ) {
+            if last_modified.timestamp() <= since.timestamp() {
+                return Precondition::NotModified;
+            }
+        }
+
+        Precondition::Passed
+    }
+
+    /// Sets `ETag` and `Last-Modified`
+    pub fn set_headers<R, B>(&self, response: &mut R) -> Result<(), BoxError>
+    where
+        R: SetResponse<B>,
+    {
+        if let Some(etag) = &self.etag {
+            response.set_typed_header(&ETag(etag.clone()))?;
+        }
+
+        if let Some(last_modified) = self.last_modified {
+            response.set_typed_header(&LastModified(last_modified))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the status by [`Validators::evaluate`], the validators except for 412, and the
+    /// body for 200 only
+    pub fn set_response<Q, R, B>(
+        &self,
+        request: &Request<Q>,
+        response: &mut R,
+        body: Vec<u8>,
+    ) -> Result<Precondition, BoxError>
+    where
+        R: SetResponse<B, Error = http::Error>,
+        B: From<Vec<u8>>,
+    {
+        let precondition = self.evaluate(request);
+
+        response.set_status(precondition.status_code())?;
+
+        match precondition {
+            Precondition::Passed => {
+                self.set_headers(response)?;
+                response.set_body(body.into());
+            }
+            Precondition::NotModified => {
+                self.set_headers(response)?;
+                response.set_body(Vec::new().into());
+            }
+            Precondition::Failed => response.set_body(Vec::new().into()),
+        }
+
+        Ok(precondition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use http::{header, Method, Request, Response, StatusCode};
+
+    use super::{strong_etag, Precondition, Validators};
+    use crate::headers::EntityTag;
+
+    fn request(method: Method, headers: &[(header::HeaderName, &str)]) -> Request<()> {
+        let mut builder = Request::builder().method(method).uri("/catalog");
+
+        for (name, value) in headers {
+            builder = builder.header(name, *value);
+        }
+
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let last_modified = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let validators = Validators::new()
+            .etag(EntityTag::strong("v2"))
+            .last_modified(last_modified);
+
+        let before = "Tue, 02 Jan 2024 03:04:04 GMT";
+        let at = "Tue, 02 Jan 2024 03:04:05 GMT";
+
+        let evaluate = |method: Method, headers: &[(header::HeaderName, &str)]| {
+            validators.evaluate(&request(method, headers))
+        };
+
+        assert_eq!(evaluate(Method::GET, &[]), Precondition::Passed);
+
+        // If-None-Match
+        assert_eq!(
+            evaluate(Method::GET, &[(header::IF_NONE_MATCH, r#"W/"v2""#)]),
+            Precondition::NotModified
+        );
+        assert_eq!(
+            evaluate(Method::PUT, &[(header::IF_NONE_MATCH, "*")]),
+            Precondition::Failed
+        );
+        assert_eq!(
+            evaluate(Method::GET, &[(header::IF_NONE_MATCH, r#""v1""#)]),
+            Precondition::Passed
+        );
+
+        // If-Modified-Since, ignored with If-None-Match and for other methods
+        assert_eq!(
+            evaluate(Method::GET, &[(header::IF_MODIFIED_SINCE, at)]),
+            Precondition::NotModified
+        );
+        assert_eq!(
+            evaluate(Method::GET, &[(header::IF_MODIFIED_SINCE, before)]),
+            Precondition::Passed
+        );
+        assert_eq!(
+            evaluate(
+                Method::GET,
+                &[
+                    (header::IF_NONE_MATCH, r#""v1""#),
+                    (header::IF_MODIFIED_SINCE, at)
+                ]
+            ),
+            Precondition::Passed
+        );
+        assert_eq!(
+            evaluate(Method::POST, &[(header::IF_MODIFIED_SINCE, at)]),
+            Precondition::Passed
+        );
+
+        // If-Match, by the strong comparison
+        assert_eq!(
+            evaluate(Method::PUT, &[(header::IF_MATCH, r#""v2""#)]),
+            Precondition::Passed
+        );
+        assert_eq!(
+            evaluate(Method::PUT, &[(header::IF_MATCH, r#"W/"v2""#)]),
+            Precondition::Failed
+        );
+
+        // `*` matches an existing representation, with or without `ETag`
+        let put = |validators: Validators, headers: &[(header::HeaderName, &str)]| {
+            validators.evaluate(&request(Method::PUT, headers))
+        };
+
+        assert_eq!(
+            put(Validators::new(), &[(header::IF_MATCH, "*")]),
+            Precondition::Passed
+        );
+        assert_eq!(
+            put(Validators::new().exists(false), &[(header::IF_MATCH, "*")]),
+            Precondition::Failed
+        );
+        assert_eq!(
+            put(
+                validators.clone().exists(false),
+                &[(header::IF_MATCH, r#""v2""#)]
+            ),
+            Precondition::Failed
+        );
+        assert_eq!(
+            put(Validators::new(), &[(header::IF_NONE_MATCH, "*")]),
+            Precondition::Failed
+        );
+        assert_eq!(
+            put(
+                Validators::new().exists(false),
+                &[(header::IF_NONE_MATCH, "*")]
+            ),
+            Precondition::Passed
+        );
+
+        // If-Unmodified-Since, ignored with If-Match
+        assert_eq!(
+            evaluate(Method::PUT, &[(header::IF_UNMODIFIED_SINCE, before)]),
+            Precondition::Failed
+        );
+        assert_eq!(
+            evaluate(Method::PUT, &[(header::IF_UNMODIFIED_SINCE, at)]),
+            Precondition::Passed
+        );
+        assert_eq!(
+            evaluate(
+                Method::PUT,
+                &[
+                    (header::IF_MATCH, "*"),
+                    (header::IF_UNMODIFIED_SINCE, before)
+                ]
+            ),
+            Precondition::Passed
+        );
+
+        // 412 takes precedence over 304
+        assert_eq!(
+            evaluate(
+                Method::GET,
+                &[
+                    (header::IF_MATCH, r#""v1""#),
+                    (header::IF_NONE_MATCH, r#""v2""#)
+                ]
+            ),
+            Precondition::Failed
+        );
+
+        // invalid dates are ignored
+        assert_eq!(
+            evaluate(Method::GET, &[(header::IF_MODIFIED_SINCE, "yesterday")]),
+            Precondition::Passed
+        );
+
+        // sub-second precision is not sent
+        let validators =
+            Validators::new().last_modified(last_modified + Duration::milliseconds(500));
+
+        assert_eq!(
+            validators.evaluate(&request(Method::GET, &[(header::IF_MODIFIED_SINCE, at)])),
+            Precondition::NotModified
+        );
+    }
+
+    #[test]
+    fn test_set_response() {
+        let body = br#"{"items":[]}"#.to_vec();
+        let etag = strong_etag(&body);
+
+        assert!(!etag.is_weak());
+        assert_eq!(etag, strong_etag(&body));
+        assert_ne!(etag, strong_etag(b"{}"));
+
+        let validators = Validators::from_body(&body);
+
+        let mut response = Response::new(Vec::new());
+        let precondition = validators
+            .set_response(&request(Method::GET, &[]), &mut response, body.clone())
+            .unwrap();
+
+        assert_eq!(precondition, Precondition::Passed);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), &body);
+
+        let sent_etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let mut response = Response::new(Vec::new());
+        validators
+            .set_response(
+                &request(Method::GET, &[(header::IF_NONE_MATCH, &sent_etag)]),
+                &mut response,
+                body.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], sent_etag.as_str());
+        assert!(response.body().is_empty());
+
+        let mut response = Response::new(Vec::new());
+        validators
+            .set_response(
+                &request(Method::PUT, &[(header::IF_MATCH, r#""stale""#)]),
+                &mut response,
+                body,
+            )
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert!(response.headers().get(header::ETAG).is_none());
+    }
+}
@@ -1,14 +1,85 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{fmt::Display, str::FromStr};
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use http::{
     header::{self, HeaderName, HeaderValue},
     HeaderMap, Request,
 };
 use itertools::Itertools;
+use percent_encoding::percent_decode_str;
+use serde::{de::DeserializeOwned, Serialize};
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CookieError {
+    #[error("Missing '=' in cookie pair: {0}")]
+    MissingEquals(String),
+    #[error("Invalid cookie name: {0}")]
+    InvalidName(String),
+    #[error("Invalid cookie value of {name}: {value}")]
+    InvalidValue { name: String, value: String },
+}
+
+/// Typed access to a value of [`Cookie`] or [`crate::SetCookie`]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CookieValueError {
+    #[error("Missing cookie: {0}")]
+    Missing(String),
+    #[error("Cookie {name} is unparsable: {message}")]
+    Unparsable { name: String, message: String },
+}
+
+pub(crate) fn parse_value<T>(name: &str, value: Option<&str>) -> Result<T, CookieValueError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = value.ok_or_else(|| CookieValueError::Missing(name.to_owned()))?;
+
+    value
+        .parse()
+        .map_err(|e: T::Err| CookieValueError::Unparsable {
+            name: name.to_owned(),
+            message: e.to_string(),
+        })
+}
+
+/// base64url of JSON as written by [`encode_json`], or percent-encoded JSON
+pub(crate) fn decode_json<T>(name: &str, value: Option<&str>) -> Result<T, CookieValueError>
+where
+    T: DeserializeOwned,
+{
+    let value = value.ok_or_else(|| CookieValueError::Missing(name.to_owned()))?;
+
+    if let Some(x) = URL_SAFE_NO_PAD
+        .decode(value)
+        .ok()
+        .and_then(|buf| serde_json::from_slice(&buf).ok())
+    {
+        return Ok(x);
+    }
+
+    let json = percent_decode_str(value).decode_utf8_lossy();
+
+    serde_json::from_str(&json).map_err(|e| CookieValueError::Unparsable {
+        name: name.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+/// base64url without padding, which consists of cookie-octets only
+pub(crate) fn encode_json<T>(value: &T) -> serde_json::Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(URL_SAFE_NO_PAD.encode(serde_json::to_vec(value)?))
+}
+
+/// `Cookie` request header, RFC 6265
+///
+/// Keeps cookies in the order sent, a name can appear more than once
 #[derive(Debug, Default, Clone)]
 pub struct Cookie {
-    inner: HashMap<String, String>,
+    inner: Vec<(String, String)>,
 }
 
 impl Cookie {
@@ -16,55 +87,167 @@ impl Cookie {
         Default::default()
     }
 
+    /// Skips invalid pairs, use [`Cookie::try_parse`] to get them reported
     pub fn parse(cookie_str: &str) -> Self {
-        let mut inner = HashMap::new();
+        let inner = pairs(cookie_str).filter_map(Result::ok).collect();
 
-        // madome_access_token=avchdef; madome_refresh_token=qwehkdfsjd
-        for a in cookie_str.split(';') {
-            let mut a = a.split('=');
+        Self { inner }
+    }
 
-            if let Some(key) = a.next() {
-                let value = a.next().unwrap_or("");
+    pub fn try_parse(cookie_str: &str) -> Result<Self, CookieError> {
+        let inner = pairs(cookie_str).collect::<Result<_, _>>()?;
+
+        Ok(Self { inner })
+    }
 
-                inner.insert(key.trim().to_string(), value.to_string());
+    /// Replaces the cookies of the same name
+    pub fn add(&mut self, key: &str, value: &str) {
+        let mut replaced = false;
+
+        self.inner.retain_mut(|(k, v)| {
+            if k != key {
+                return true;
             }
-        }
 
-        Self { inner }
+            if replaced {
+                return false;
+            }
+
+            *v = value.to_string();
+            replaced = true;
+
+            true
+        });
+
+        if !replaced {
+            self.inner.push((key.to_string(), value.to_string()));
+        }
     }
 
-    pub fn add(&mut self, key: &str, value: &str) {
-        self.inner.insert(key.to_string(), value.to_string());
+    /// Keeps the cookies of the same name
+    pub fn append(&mut self, key: &str, value: &str) {
+        self.inner.push((key.to_string(), value.to_string()));
     }
 
-    #[allow(dead_code)]
+    /// The first one if the name is duplicated
     pub fn get(&self, key: &str) -> Option<&str> {
-        self.inner.get(key).map(|st| st as &str)
+        self.inner
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.inner
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_as<T>(&self, key: &str) -> Result<T, CookieValueError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        parse_value(key, self.get(key))
+    }
+
+    pub fn get_json<T>(&self, key: &str) -> Result<T, CookieValueError>
+    where
+        T: DeserializeOwned,
+    {
+        decode_json(key, self.get(key))
     }
 
     pub fn get2(&self, key1: &str, key2: &str) -> Option<(&str, &str)> {
         self.get(key1).and_then(|x| Some((x, self.get(key2)?)))
     }
 
+    /// Removes all the cookies of the name, returns the first one
     pub fn take(&mut self, key: &str) -> Option<String> {
-        self.inner.remove(key)
+        let mut taken = None;
+
+        self.inner.retain_mut(|(k, v)| {
+            if k != key {
+                return true;
+            }
+
+            if taken.is_none() {
+                taken.replace(std::mem::take(v));
+            }
+
+            false
+        });
+
+        taken
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.inner.iter().map(|(k, v)| (k.as_str(), v.as_str()))
     }
 }
 
+/// madome_access_token=avchdef; madome_refresh_token="qwe=="
+fn pairs(cookie_str: &str) -> impl Iterator<Item = Result<(String, String), CookieError>> + '_ {
+    cookie_str
+        .split(';')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|pair| {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| CookieError::MissingEquals(pair.to_string()))?;
+
+            let name = name.trim();
+
+            if name.is_empty() || !name.bytes().all(is_token_char) {
+                return Err(CookieError::InvalidName(name.to_string()));
+            }
+
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('"')
+                .and_then(|x| x.strip_suffix('"'))
+                .unwrap_or(value);
+
+            if !unquoted.bytes().all(is_cookie_octet) {
+                return Err(CookieError::InvalidValue {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                });
+            }
+
+            Ok((name.to_string(), unquoted.to_string()))
+        })
+}
+
+/// tchar of RFC 7230
+fn is_token_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)
+}
+
+/// US-ASCII except CTLs, whitespace, DQUOTE, comma, semicolon and backslash
+fn is_cookie_octet(c: u8) -> bool {
+    matches!(c, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
 impl<T> From<&Request<T>> for Cookie {
     fn from(request: &Request<T>) -> Self {
         request.headers().into()
     }
 }
 
+/// Joins every `Cookie` header
 impl From<&HeaderMap> for Cookie {
     fn from(headers: &HeaderMap) -> Self {
-        let cookie_str = headers
-            .get(header::COOKIE)
-            .and_then(|a| a.to_str().ok())
-            .unwrap_or("");
+        let inner = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|a| a.to_str().ok())
+            .flat_map(|x| Self::parse(x).inner)
+            .collect();
 
-        Self::parse(cookie_str)
+        Self { inner }
     }
 }
 
@@ -74,7 +257,7 @@ impl Display for Cookie {
             .inner
             .iter()
             .map(|(key, value)| format!("{}={}", key, value))
-            .join(";");
+            .join("; ");
 
         write!(f, "{}", r)
     }
@@ -82,9 +265,13 @@ impl Display for Cookie {
 
 impl FromIterator<(String, String)> for Cookie {
     fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
-        Self {
-            inner: HashMap::from_iter(iter),
+        let mut cookie = Self::new();
+
+        for (key, value) in iter {
+            cookie.add(&key, &value);
         }
+
+        cookie
     }
 }
 
@@ -123,3 +310,72 @@ impl From<Cookie> for (HeaderName, HeaderValue) {
         )
     }
 }
+
+#[test]
+fn cookie_parse() {
+    let cookie = Cookie::parse(
+        r#"token=eyJhbGciOi.eyJzdWIi.c2ln==; theme="dark"; a=1; a=2; empty=; bad=x y"#,
+    );
+
+    assert_eq!(cookie.get("token"), Some("eyJhbGciOi.eyJzdWIi.c2ln=="));
+    assert_eq!(cookie.get("theme"), Some("dark"));
+    assert_eq!(cookie.get("a"), Some("1"));
+    assert_eq!(cookie.get_all("a").collect::<Vec<_>>(), ["1", "2"]);
+    assert_eq!(cookie.get("empty"), Some(""));
+    assert_eq!(cookie.get("bad"), None);
+    assert_eq!(
+        cookie.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+        ["token", "theme", "a", "a", "empty"]
+    );
+
+    assert_eq!(
+        Cookie::try_parse("a=1; bad=x y").unwrap_err(),
+        CookieError::InvalidValue {
+            name: "bad".to_owned(),
+            value: "x y".to_owned()
+        }
+    );
+    assert_eq!(
+        Cookie::try_parse("a=1; b").unwrap_err(),
+        CookieError::MissingEquals("b".to_owned())
+    );
+    assert_eq!(
+        Cookie::try_parse("a b=1").unwrap_err(),
+        CookieError::InvalidName("a b".to_owned())
+    );
+
+    let mut cookie = Cookie::parse("a=1; b=2; a=3");
+
+    cookie.add("a", "4");
+
+    assert_eq!(cookie.to_string(), "a=4; b=2");
+    assert_eq!(cookie.take("a"), Some("4".to_owned()));
+    assert_eq!(cookie.to_string(), "b=2");
+}
+
+#[test]
+fn cookie_typed_values() {
+    let cookie = Cookie::parse(&format!(
+        "n=12; bad=x; prefs={}; raw=%7B%22a%22%3A1%7D",
+        encode_json(&serde_json::json!({ "theme": "dark" })).unwrap()
+    ));
+
+    assert_eq!(cookie.get_as::<u32>("n"), Ok(12));
+    assert_eq!(
+        cookie.get_as::<u32>("none"),
+        Err(CookieValueError::Missing("none".to_owned()))
+    );
+    assert!(matches!(
+        cookie.get_as::<u32>("bad"),
+        Err(CookieValueError::Unparsable { .. })
+    ));
+    assert_eq!(
+        cookie.get_json::<serde_json::Value>("prefs").unwrap(),
+        serde_json::json!({ "theme": "dark" })
+    );
+    assert_eq!(
+        cookie.get_json::<serde_json::Value>("raw").unwrap(),
+        serde_json::json!({ "a": 1 })
+    );
+    assert!(cookie.get_json::<serde_json::Value>("bad").is_err());
+}
@@ -0,0 +1,402 @@
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{Cookie, SetCookie, SetCookieOptions};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const NONCE_LEN: usize = 12;
+
+/// Bytes, as long as the derived keys
+const MIN_SECRET_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Secret must be at least {MIN_SECRET_LEN} bytes, got {0}")]
+pub struct KeyError(usize);
+
+#[derive(Clone)]
+struct Secret {
+    signing: [u8; 32],
+    encryption: [u8; 32],
+}
+
+impl Secret {
+    /// Derives independent keys for signing and encryption from one secret
+    fn derive(secret: &[u8]) -> Self {
+        let derive = |label: &[u8]| -> [u8; 32] {
+            let mut mac = hmac(secret);
+            mac.update(label);
+            mac.finalize().into_bytes().into()
+        };
+
+        Self {
+            signing: derive(b"http-util cookie signing"),
+            encryption: derive(b"http-util cookie encryption"),
+        }
+    }
+}
+
+/// Key of [`SignedJar`] and [`PrivateJar`]
+///
+/// Cookies are always written with the current secret and read with the current or any of the
+/// previous ones, so a secret can be rotated without logging everyone out.
+/// Secrets are at least 32 bytes.
+///
+/// ```no_run
+/// # use http_util::Key;
+/// # fn main() -> Result<(), http_util::KeyError> {
+/// # let new_secret = &[1; 32];
+/// # let old_secret = &[0; 32];
+/// let key = Key::new(new_secret)?.previous(old_secret)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Key {
+    current: Secret,
+    previous: Vec<Secret>,
+}
+
+impl Key {
+    pub fn new(secret: &[u8]) -> Result<Self, KeyError> {
+        Ok(Self {
+            current: Secret::derive(check_len(secret)?),
+            previous: Vec::new(),
+        })
+    }
+
+    /// Random 32 bytes secret, returned with the key to be stored
+    pub fn generate() -> (Self, Vec<u8>) {
+        let mut secret = vec![0; MIN_SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+
+        let key = Self::new(&secret).expect("long enough secret");
+
+        (key, secret)
+    }
+
+    /// Accepts cookies written with the secret
+    pub fn previous(mut self, secret: &[u8]) -> Result<Self, KeyError> {
+        self.previous.push(Secret::derive(check_len(secret)?));
+
+        Ok(self)
+    }
+
+    fn secrets(&self) -> impl Iterator<Item = &Secret> {
+        std::iter::once(&self.current).chain(self.previous.iter())
+    }
+}
+
+fn check_len(secret: &[u8]) -> Result<&[u8], KeyError> {
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(KeyError(secret.len()));
+    }
+
+    Ok(secret)
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key")
+            .field("previous", &self.previous.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cookies with an HMAC-SHA256 tag, `{tag}.{base64url(value)}`
+///
+/// The value is encoded so that any string round-trips and can not inject attributes.
+/// The tag covers the name as well, so a value can not be moved to another cookie.
+/// Values which fail the verification are treated as absent.
+#[derive(Debug)]
+pub struct SignedJar<'a> {
+    key: &'a Key,
+    cookie: Cookie,
+    set_cookie: SetCookie,
+}
+
+impl<'a> SignedJar<'a> {
+    pub fn new(key: &'a Key, cookie: Cookie) -> Self {
+        Self {
+            key,
+            cookie,
+            set_cookie: SetCookie::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.cookie
+            .get_all(name)
+            .find_map(|signed| self.verify(name, signed))
+    }
+
+    pub fn set(
+        mut self,
+        name: impl Into<String>,
+        value: impl AsRef<str>,
+        options: SetCookieOptions,
+    ) -> Self {
+        let name = name.into();
+        let signed = self.sign(&name, value.as_ref());
+
+        self.set_cookie = self.set_cookie.set(name, signed, options);
+
+        self
+    }
+
+    pub fn set_cookie(&self) -> &SetCookie {
+        &self.set_cookie
+    }
+
+    pub fn into_set_cookie(self) -> SetCookie {
+        self.set_cookie
+    }
+
+    fn sign(&self, name: &str, value: &str) -> String {
+        let tag = tag(&self.key.current, name, value).finalize().into_bytes();
+
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(tag),
+            URL_SAFE_NO_PAD.encode(value)
+        )
+    }
+
+    fn verify(&self, name: &str, signed: &str) -> Option<String> {
+        let (tag_str, encoded) = signed.split_once('.')?;
+        let expected = URL_SAFE_NO_PAD.decode(tag_str).ok()?;
+        let value = String::from_utf8(URL_SAFE_NO_PAD.decode(encoded).ok()?).ok()?;
+
+        self.key
+            .secrets()
+            .any(|secret| tag(secret, name, &value).verify_slice(&expected).is_ok())
+            .then_some(value)
+    }
+}
+
+fn hmac(key: &[u8]) -> HmacSha256 {
+    <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length")
+}
+
+fn tag(secret: &Secret, name: &str, value: &str) -> HmacSha256 {
+    let mut mac = hmac(&secret.signing);
+
+    // the length prefix keeps ("ab", "c") and ("a", "bc") apart
+    mac.update(&(name.len() as u64).to_be_bytes());
+    mac.update(name.as_bytes());
+    mac.update(value.as_bytes());
+
+    mac
+}
+
+/// Cookies encrypted with AES-256-GCM, `base64url(nonce || ciphertext)`
+///
+/// The name is the associated data, so a value can not be moved to another cookie.
+/// Values which fail the decryption are treated as absent.
+#[derive(Debug)]
+pub struct PrivateJar<'a> {
+    key: &'a Key,
+    cookie: Cookie,
+    set_cookie: SetCookie,
+}
+
+impl<'a> PrivateJar<'a> {
+    pub fn new(key: &'a Key, cookie: Cookie) -> Self {
+        Self {
+            key,
+            cookie,
+            set_cookie: SetCookie::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.cookie
+            .get_all(name)
+            .find_map(|encrypted| self.decrypt(name, encrypted))
+    }
+
+    pub fn set(
+        mut self,
+        name: impl Into<String>,
+        value: impl AsRef<str>,
+        options: SetCookieOptions,
+    ) -> Self {
+        let name = name.into();
+        let encrypted = self.encrypt(&name, value.as_ref());
+
+        self.set_cookie = self.set_cookie.set(name, encrypted, options);
+
+        self
+    }
+
+    pub fn set_cookie(&self) -> &SetCookie {
+        &self.set_cookie
+    }
+
+    pub fn into_set_cookie(self) -> SetCookie {
+        self.set_cookie
+    }
+
+    fn encrypt(&self, name: &str, value: &str) -> String {
+        let cipher = Aes256Gcm::new(&self.key.current.encryption.into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: name.as_bytes(),
+        };
+
+        // fails only if the message is too long for AES-GCM, far more than a cookie can hold
+        let ciphertext = cipher
+            .encrypt(&nonce, payload)
+            .expect("cookie value is too long to encrypt");
+
+        let mut buf = nonce.to_vec();
+        buf.extend_from_slice(&ciphertext);
+
+        URL_SAFE_NO_PAD.encode(buf)
+    }
+
+    fn decrypt(&self, name: &str, encrypted: &str) -> Option<String> {
+        let buf = URL_SAFE_NO_PAD.decode(encrypted).ok()?;
+
+        if buf.len() < NONCE_LEN {
+            return None;
+        }
+
+        let (nonce, ciphertext) = buf.split_at(NONCE_LEN);
+        let nonce = Nonce::from_slice(nonce);
+
+        let plaintext = self.key.secrets().find_map(|secret| {
+            let payload = Payload {
+                msg: ciphertext,
+                aad: name.as_bytes(),
+            };
+
+            Aes256Gcm::new(&secret.encryption.into())
+                .decrypt(nonce, payload)
+                .ok()
+        })?;
+
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, KeyError, PrivateJar, SignedJar};
+    use crate::{Cookie, SetCookieOptions};
+
+    const SECRET: &[u8] = b"secret of at least thirty-two bytes";
+    const NEW_SECRET: &[u8] = b"new secret of at least thirty-two bytes";
+
+    /// Turns the `Set-Cookie` of a jar into a `Cookie`
+    fn round_trip(set_cookie: &crate::SetCookie, names: &[&str]) -> Cookie {
+        names
+            .iter()
+            .filter_map(|name| Some((*name, set_cookie.get(name)?)))
+            .collect()
+    }
+
+    #[test]
+    fn test_signed_jar() {
+        let key = Key::new(SECRET).unwrap();
+
+        let jar =
+            SignedJar::new(&key, Cookie::new()).set("session", "user-12", SetCookieOptions::new());
+
+        let signed = jar.set_cookie().get("session").unwrap();
+
+        assert!(signed.ends_with(".dXNlci0xMg"));
+
+        let cookie = round_trip(jar.set_cookie(), &["session"]);
+
+        assert_eq!(
+            SignedJar::new(&key, cookie.clone()).get("session"),
+            Some("user-12".to_owned())
+        );
+
+        // tampered
+        let tampered = Cookie::parse(&format!("session={}", signed.replace("Mg", "Mw")));
+        assert_eq!(SignedJar::new(&key, tampered).get("session"), None);
+
+        // moved to another name
+        let moved = Cookie::parse(&format!("other={signed}"));
+        assert_eq!(SignedJar::new(&key, moved).get("other"), None);
+
+        // rotated
+        let rotated = Key::new(NEW_SECRET).unwrap().previous(SECRET).unwrap();
+        assert_eq!(
+            SignedJar::new(&rotated, cookie.clone()).get("session"),
+            Some("user-12".to_owned())
+        );
+        assert_eq!(
+            SignedJar::new(&Key::new(NEW_SECRET).unwrap(), cookie).get("session"),
+            None
+        );
+
+        // separators and non-ASCII round-trip without injecting attributes
+        let value = "a; Path=/admin, b =\"사진\"";
+        let jar = SignedJar::new(&key, Cookie::new()).set("pref", value, SetCookieOptions::new());
+        let header_value = jar.set_cookie().iter().next().unwrap().1.clone();
+
+        assert!(!header_value.to_str().unwrap().contains("Path"));
+
+        let cookie = round_trip(jar.set_cookie(), &["pref"]);
+
+        assert_eq!(
+            SignedJar::new(&key, cookie).get("pref"),
+            Some(value.to_owned())
+        );
+    }
+
+    #[test]
+    fn test_key_length() {
+        assert_eq!(Key::new(b"").err(), Some(KeyError(0)));
+        assert_eq!(Key::new(&[0; 31]).err(), Some(KeyError(31)));
+        assert!(Key::new(&[0; 32]).is_ok());
+        assert_eq!(
+            Key::new(SECRET).unwrap().previous(b"short").err(),
+            Some(KeyError(5))
+        );
+    }
+
+    #[test]
+    fn test_private_jar() {
+        let (key, secret) = Key::generate();
+
+        let jar = PrivateJar::new(&key, Cookie::new()).set(
+            "refresh",
+            "token=abc",
+            SetCookieOptions::new(),
+        );
+
+        let encrypted = jar.set_cookie().get("refresh").unwrap().to_owned();
+
+        assert!(!encrypted.contains("abc"));
+
+        let cookie = round_trip(jar.set_cookie(), &["refresh"]);
+
+        assert_eq!(
+            PrivateJar::new(&key, cookie.clone()).get("refresh"),
+            Some("token=abc".to_owned())
+        );
+
+        let moved = Cookie::parse(&format!("other={encrypted}"));
+        assert_eq!(PrivateJar::new(&key, moved).get("other"), None);
+
+        let garbage = Cookie::parse("refresh=AAAA");
+        assert_eq!(PrivateJar::new(&key, garbage).get("refresh"), None);
+
+        let rotated = Key::new(NEW_SECRET).unwrap().previous(&secret).unwrap();
+        assert_eq!(
+            PrivateJar::new(&rotated, cookie).get("refresh"),
+            Some("token=abc".to_owned())
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    io::{Read, Write},
+    net::IpAddr,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use http::Uri;
+use serde::{Deserialize, Serialize};
+
+use crate::{Cookie, SetCookie, SetCookieOptions};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    /// Lowercase, without the leading `.`
+    domain: String,
+    /// Set without `Domain`, sent to the exact host only
+    host_only: bool,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    /// Unix timestamp in seconds, a session cookie if absent
+    expires: Option<i64>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.expires, Some(expires) if expires <= now.timestamp())
+    }
+
+    fn matches(&self, url: &Uri, now: DateTime<Utc>) -> bool {
+        let host = url.host().unwrap_or_default().to_lowercase();
+
+        let host_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+
+        host_matches
+            && path_match(url.path(), &self.path)
+            && (!self.secure || url.scheme_str() == Some("https"))
+            && !self.is_expired(now)
+    }
+}
+
+/// Client-side cookies of RFC 6265, fed by `Set-Cookie` of responses
+///
+/// A `Domain` of a single label such as `com` is rejected, but the Public Suffix List is not
+/// consulted, so `Domain=co.uk` from `www.example.co.uk` is stored and sent to every site
+/// under `co.uk`. Don't share a store across untrusted sites relying on registrable domains.
+///
+/// ```no_run
+/// # use http::{Response, Uri};
+/// # use http_util::{CookieStore, SetCookie};
+/// # fn handle(store: &mut CookieStore, url: Uri, response: Response<()>, next_url: Uri) {
+/// store.store(&url, &SetCookie::from_headers(response.headers()));
+///
+/// let cookie = store.cookie(&next_url);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CookieStore {
+    /// In creation order
+    cookies: Vec<StoredCookie>,
+}
+
+impl CookieStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Stores `Set-Cookie` of a response to the request url
+    pub fn store(&mut self, url: &Uri, set_cookie: &SetCookie) {
+        self.store_at(url, set_cookie, Utc::now());
+    }
+
+    /// `Cookie` header for a request to the url
+    pub fn cookie(&self, url: &Uri) -> Cookie {
+        self.cookie_at(url, Utc::now())
+    }
+
+    pub fn clear_expired(&mut self) {
+        let now = Utc::now();
+
+        self.cookies.retain(|x| !x.is_expired(now));
+    }
+
+    pub fn save_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn load_json<R: Read>(reader: R) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+
+    fn store_at(&mut self, url: &Uri, set_cookie: &SetCookie, now: DateTime<Utc>) {
+        let host = url.host().unwrap_or_default().to_lowercase();
+        let secure_url = url.scheme_str() == Some("https");
+
+        for (name, value, options) in set_cookie.entries() {
+            // only a secure origin can set `Secure`, RFC 6265bis 5.7
+            if options.secure && !secure_url {
+                continue;
+            }
+
+            let (domain, host_only) = match options.domain.as_deref() {
+                Some(domain) if !domain.is_empty() => {
+                    let domain = domain.trim_start_matches('.').to_lowercase();
+
+                    // a cookie for another site, an IP address matches itself only
+                    if !domain_match(&host, &domain) || (is_ip(&host) && host != domain) {
+                        continue;
+                    }
+
+                    // `Domain=com` would be sent to every site under it, RFC 6265 5.3
+                    if is_top_level(&domain) {
+                        if host != domain {
+                            continue;
+                        }
+
+                        (domain, true)
+                    } else {
+                        (domain, false)
+                    }
+                }
+                _ => (host.clone(), true),
+            };
+
+            let path = match options.path.as_deref() {
+                Some(path) if path.starts_with('/') => path.to_owned(),
+                _ => default_path(url.path()),
+            };
+
+            let cookie = StoredCookie {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                domain,
+                host_only,
+                path,
+                secure: options.secure,
+                http_only: options.http_only,
+                expires: expires(options, now),
+            };
+
+            let existing = self.cookies.iter().position(|x| {
+                x.name == cookie.name && x.domain == cookie.domain && x.path == cookie.path
+            });
+
+            match existing {
+                // `Max-Age=0` or `Expires` in the past deletes the cookie
+                Some(i) if cookie.is_expired(now) => {
+                    self.cookies.remove(i);
+                }
+                // keeps the creation order of the old one
+                Some(i) => self.cookies[i] = cookie,
+                None if cookie.is_expired(now) => {}
+                None => self.cookies.push(cookie),
+            }
+        }
+    }
+
+    fn cookie_at(&self, url: &Uri, now: DateTime<Utc>) -> Cookie {
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|x| x.matches(url, now))
+            .collect::<Vec<_>>();
+
+        // longer paths first, stable for the creation order
+        cookies.sort_by_key(|x| std::cmp::Reverse(x.path.len()));
+
+        let mut cookie = Cookie::new();
+
+        for x in cookies {
+            cookie.append(&x.name, &x.value);
+        }
+
+        cookie
+    }
+}
+
+/// `Max-Age` takes precedence over `Expires`
+fn expires(options: &SetCookieOptions, now: DateTime<Utc>) -> Option<i64> {
+    match (options.max_age, options.expires) {
+        (Some(max_age), _) if max_age <= 0 => Some(i64::MIN),
+        (Some(max_age), _) => Some(
+            now.checked_add_signed(Duration::seconds(max_age))
+                .map(|x| x.timestamp())
+                .unwrap_or(i64::MAX),
+        ),
+        (None, Some(expires)) => Some(expires.timestamp()),
+        (None, None) => None,
+    }
+}
+
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+}
+
+fn is_ip(host: &str) -> bool {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok()
+}
+
+/// A single label such as `com` or `localhost`
+fn is_top_level(domain: &str) -> bool {
+    !domain.contains('.')
+}
+
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    let request_path = if request_path.is_empty() {
+        "/"
+    } else {
+        request_path
+    };
+
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// Directory of the request path
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(0) | None => "/".to_owned(),
+        Some(i) => request_path[..i].to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use http::Uri;
+
+    use super::CookieStore;
+    use crate::{SetCookie, SetCookieOptions};
+
+    fn url(st: &str) -> Uri {
+        st.parse().unwrap()
+    }
+
+    fn set_cookie(header_values: &[&str]) -> SetCookie {
+        SetCookie::from(header_values.iter())
+    }
+
+    #[test]
+    fn test_cookie_store() {
+        let now = Utc::now();
+        let mut store = CookieStore::new();
+
+        store.store_at(
+            &url("https://api.example.com/auth/login"),
+            &set_cookie(&[
+                "session=abc; Path=/; Secure; HttpOnly",
+                "host=1",
+                "wide=2; Domain=.Example.com; Path=/",
+                "other=3; Domain=evil.com",
+                "short=4; Max-Age=60",
+            ]),
+            now,
+        );
+
+        // `other` is for another site
+        assert_eq!(store.len(), 4);
+
+        let cookie = store.cookie_at(&url("https://api.example.com/auth/refresh"), now);
+
+        assert_eq!(cookie.to_string(), "host=1; short=4; session=abc; wide=2");
+
+        // not secure, other host, out of the default path
+        let cookie = store.cookie_at(&url("http://www.example.com/"), now);
+
+        assert_eq!(cookie.to_string(), "wide=2");
+
+        // expired
+        let later = now + Duration::seconds(61);
+        let cookie = store.cookie_at(&url("https://api.example.com/auth/x"), later);
+
+        assert_eq!(cookie.get("short"), None);
+
+        // deleted
+        store.store_at(
+            &url("https://api.example.com/"),
+            &SetCookie::new().set("session", "", SetCookieOptions::new().path("/").max_age(0)),
+            now,
+        );
+
+        let cookie = store.cookie_at(&url("https://api.example.com/auth/x"), now);
+
+        assert_eq!(cookie.get("session"), None);
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_cookie_store_rejected() {
+        let now = Utc::now();
+        let mut store = CookieStore::new();
+
+        store.store_at(
+            &url("https://www.example.com/"),
+            &set_cookie(&["tld=1; Domain=com", "dot=2; Domain=.com"]),
+            now,
+        );
+        store.store_at(
+            &url("https://192.168.0.1/"),
+            &set_cookie(&["ip=4; Domain=168.0.1"]),
+            now,
+        );
+        store.store_at(
+            &url("http://example.com/"),
+            &set_cookie(&["insecure=5; Secure"]),
+            now,
+        );
+
+        assert!(store.is_empty());
+
+        assert_eq!(
+            store.cookie_at(&url("https://other.com/"), now).to_string(),
+            ""
+        );
+
+        // the host itself, sent to the exact host only
+        store.store_at(
+            &url("https://localhost/"),
+            &set_cookie(&["local=6; Domain=localhost"]),
+            now,
+        );
+        store.store_at(
+            &url("https://192.168.0.1/"),
+            &set_cookie(&["ip=7; Domain=192.168.0.1"]),
+            now,
+        );
+
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.cookie_at(&url("https://localhost/"), now).to_string(),
+            "local=6"
+        );
+        assert_eq!(
+            store
+                .cookie_at(&url("https://sub.localhost/"), now)
+                .to_string(),
+            ""
+        );
+        assert_eq!(
+            store
+                .cookie_at(&url("https://192.168.0.1/"), now)
+                .to_string(),
+            "ip=7"
+        );
+    }
+
+    #[test]
+    fn test_cookie_store_invalid_max_age() {
+        let now = Utc::now();
+        let mut store = CookieStore::new();
+
+        store.store_at(
+            &url("https://example.com/"),
+            &set_cookie(&["a=1", "b=2; Max-Age=abc", "c=3; Max-Age=+60"]),
+            now,
+        );
+
+        // ignored rather than read as `Max-Age=0`
+        store.store_at(
+            &url("https://example.com/"),
+            &set_cookie(&["a=4; Max-Age=1x"]),
+            now,
+        );
+
+        let cookie = store.cookie_at(&url("https://example.com/"), now + Duration::days(365));
+
+        assert_eq!(cookie.to_string(), "a=4; b=2; c=3");
+    }
+
+    #[test]
+    fn test_cookie_store_expires_deletes() {
+        let now = Utc::now();
+        let mut store = CookieStore::new();
+
+        store.store_at(
+            &url("https://example.com/"),
+            &set_cookie(&["a=1", "b=2"]),
+            now,
+        );
+        store.store_at(
+            &url("https://example.com/"),
+            &set_cookie(&["a=; Expires=Thu, 01-Jan-1970 00:00:01 GMT"]),
+            now,
+        );
+
+        assert_eq!(
+            store
+                .cookie_at(&url("https://example.com/"), now)
+                .to_string(),
+            "b=2"
+        );
+    }
+
+    #[test]
+    fn test_cookie_store_json() {
+        let mut store = CookieStore::new();
+
+        store.store(
+            &url("https://example.com/"),
+            &set_cookie(&["a=1; Max-Age=3600", "b=2"]),
+        );
+
+        let mut buf = Vec::new();
+        store.save_json(&mut buf).unwrap();
+
+        let loaded = CookieStore::load_json(buf.as_slice()).unwrap();
+
+        assert_eq!(loaded, store);
+        assert_eq!(
+            loaded.cookie(&url("https://example.com/")).to_string(),
+            "a=1; b=2"
+        );
+    }
+}
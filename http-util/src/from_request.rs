@@ -1,6 +1,10 @@
+use std::convert::Infallible;
+
 use http::Request;
 use hyper::Body;
 
+use crate::BoxError;
+
 #[async_trait::async_trait]
 pub trait FromRequest<'a>: Sized {
     type Parameter: Send;
@@ -64,3 +68,119 @@ where
         T::from_owned_request(param, self).await
     }
 }
+
+/// Extraction failure turns into `None`
+#[async_trait::async_trait]
+impl<'a, T> FromRequest<'a> for Option<T>
+where
+    T: FromRequest<'a> + Send,
+{
+    type Parameter = T::Parameter;
+    type Error = Infallible;
+
+    async fn from_request(
+        param: Self::Parameter,
+        request: &'a mut Request<Body>,
+    ) -> Result<Self, Self::Error> {
+        Ok(T::from_request(param, request).await.ok())
+    }
+}
+
+/// Hands the extraction error to the handler
+#[async_trait::async_trait]
+impl<'a, T, E> FromRequest<'a> for Result<T, E>
+where
+    T: FromRequest<'a, Error = E> + Send,
+    E: Send,
+{
+    type Parameter = T::Parameter;
+    type Error = Infallible;
+
+    async fn from_request(
+        param: Self::Parameter,
+        request: &'a mut Request<Body>,
+    ) -> Result<Self, Self::Error> {
+        Ok(T::from_request(param, request).await)
+    }
+}
+
+/// Extracts in order from a single request, parameters are tupled as well
+///
+/// Each element borrows the request one after another, so it can not borrow from the request
+/// and its error is boxed. Wrap an element with `Result` to get its error as it is.
+macro_rules! impl_from_request_for_tuple {
+    ($($ty:ident $param:ident $err:ident),+) => {
+        #[async_trait::async_trait]
+        impl<'a, $($ty, $param, $err),+> FromRequest<'a> for ($($ty,)+)
+        where
+            $(
+                $ty: for<'b> FromRequest<'b, Parameter = $param, Error = $err> + Send + 'static,
+                $param: Send + 'static,
+                $err: std::error::Error + Send + Sync + 'static,
+            )+
+        {
+            type Parameter = ($($param,)+);
+            type Error = BoxError;
+
+            #[allow(non_snake_case)]
+            async fn from_request(
+                ($($param,)+): Self::Parameter,
+                request: &'a mut Request<Body>,
+            ) -> Result<Self, Self::Error> {
+                $(
+                    let $ty = $ty::from_request($param, &mut *request).await?;
+                )+
+
+                Ok(($($ty,)+))
+            }
+        }
+    };
+}
+
+impl_from_request_for_tuple!(A PA EA);
+impl_from_request_for_tuple!(A PA EA, B PB EB);
+impl_from_request_for_tuple!(A PA EA, B PB EB, C PC EC);
+impl_from_request_for_tuple!(A PA EA, B PB EB, C PC EC, D PD ED);
+impl_from_request_for_tuple!(A PA EA, B PB EB, C PC EC, D PD ED, E PE EE);
+impl_from_request_for_tuple!(A PA EA, B PB EB, C PC EC, D PD ED, E PE EE, F PF EF);
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+    use serde::Deserialize;
+
+    use super::ToPayload;
+    use crate::{Query, QueryError};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Paging {
+        page: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Search {
+        q: String,
+    }
+
+    #[tokio::test]
+    async fn test_tuple_option_result() {
+        let mut request = Request::get("/?page=2").body(Default::default()).unwrap();
+
+        type Payload = (
+            Query<Paging>,
+            Option<Query<Search>>,
+            Result<Query<Search>, QueryError>,
+        );
+
+        let (Query(paging), search, result): Payload =
+            request.to_payload(((), (), ())).await.unwrap();
+
+        assert_eq!(paging, Paging { page: 2 });
+        assert!(search.is_none());
+        assert_eq!(result.unwrap_err(), QueryError::Missing("q".to_owned()));
+
+        let r: Result<(Query<Paging>, Query<Search>), _> = request.to_payload(((), ())).await;
+
+        assert_eq!(r.unwrap_err().to_string(), "Missing query parameter: q");
+    }
+}
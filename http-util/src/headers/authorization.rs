@@ -0,0 +1,86 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::header::{self, HeaderName};
+
+use super::Header;
+
+/// `Authorization` of the `Basic` or `Bearer` scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    /// RFC 7617
+    Basic { username: String, password: String },
+    /// RFC 6750
+    Bearer(String),
+}
+
+impl Authorization {
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(token.into())
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            Self::Bearer(token) => Some(token),
+            Self::Basic { .. } => None,
+        }
+    }
+}
+
+impl Header for Authorization {
+    fn name() -> HeaderName {
+        header::AUTHORIZATION
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let (scheme, credentials) = value.trim().split_once(' ')?;
+        let credentials = credentials.trim();
+
+        // the scheme is case-insensitive
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(STANDARD.decode(credentials).ok()?).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+
+            Some(Self::basic(username, password))
+        } else if scheme.eq_ignore_ascii_case("bearer") && !credentials.is_empty() {
+            Some(Self::bearer(credentials))
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Basic { username, password } => {
+                format!(
+                    "Basic {}",
+                    STANDARD.encode(format!("{username}:{password}"))
+                )
+            }
+            Self::Bearer(token) => format!("Bearer {token}"),
+        }
+    }
+}
+
+#[test]
+fn authorization() {
+    // RFC 7617 2.
+    let basic = Authorization::decode("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==").unwrap();
+
+    assert_eq!(basic, Authorization::basic("Aladdin", "open sesame"));
+    assert_eq!(basic.encode(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+
+    let bearer = Authorization::decode("bearer mF_9.B5f-4.1JqM").unwrap();
+
+    assert_eq!(bearer.token(), Some("mF_9.B5f-4.1JqM"));
+    assert_eq!(bearer.encode(), "Bearer mF_9.B5f-4.1JqM");
+
+    assert_eq!(Authorization::decode("Basic !!!"), None);
+    assert_eq!(Authorization::decode("Digest abc"), None);
+    assert_eq!(Authorization::decode("Bearer"), None);
+}
@@ -0,0 +1,206 @@
+use http::header::{self, HeaderName};
+use itertools::Itertools;
+
+use super::{quote, split_unquoted, unquote, Header};
+
+/// `Cache-Control`, RFC 9111 5.2
+///
+/// ```no_run
+/// # use http_util::CacheControl;
+/// let cache_control = CacheControl::new().public().max_age(3600).immutable();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CacheControl {
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub no_transform: bool,
+    pub only_if_cached: bool,
+    pub must_revalidate: bool,
+    pub proxy_revalidate: bool,
+    pub must_understand: bool,
+    pub public: bool,
+    pub private: bool,
+    pub immutable: bool,
+    /// Seconds
+    pub max_age: Option<u64>,
+    pub s_maxage: Option<u64>,
+    pub max_stale: Option<u64>,
+    pub min_fresh: Option<u64>,
+    pub stale_while_revalidate: Option<u64>,
+    pub stale_if_error: Option<u64>,
+    /// Unknown directives with their arguments
+    pub extensions: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn no_cache(mut self) -> Self {
+        self.no_cache = true;
+
+        self
+    }
+
+    pub fn no_store(mut self) -> Self {
+        self.no_store = true;
+
+        self
+    }
+
+    pub fn must_revalidate(mut self) -> Self {
+        self.must_revalidate = true;
+
+        self
+    }
+
+    pub fn public(mut self) -> Self {
+        self.public = true;
+
+        self
+    }
+
+    pub fn private(mut self) -> Self {
+        self.private = true;
+
+        self
+    }
+
+    pub fn immutable(mut self) -> Self {
+        self.immutable = true;
+
+        self
+    }
+
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age.replace(seconds);
+
+        self
+    }
+
+    pub fn s_maxage(mut self, seconds: u64) -> Self {
+        self.s_maxage.replace(seconds);
+
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, seconds: u64) -> Self {
+        self.stale_while_revalidate.replace(seconds);
+
+        self
+    }
+}
+
+impl Header for CacheControl {
+    fn name() -> HeaderName {
+        header::CACHE_CONTROL
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let mut r = Self::new();
+
+        for directive in split_unquoted(value, ',') {
+            let (name, argument) = match directive.split_once('=') {
+                Some((name, argument)) => (name.trim(), Some(unquote(argument))),
+                None => (directive, None),
+            };
+
+            let seconds = || argument.as_deref()?.parse::<u64>().ok();
+
+            // directive names are case-insensitive
+            match name.to_ascii_lowercase().as_str() {
+                "no-cache" => r.no_cache = true,
+                "no-store" => r.no_store = true,
+                "no-transform" => r.no_transform = true,
+                "only-if-cached" => r.only_if_cached = true,
+                "must-revalidate" => r.must_revalidate = true,
+                "proxy-revalidate" => r.proxy_revalidate = true,
+                "must-understand" => r.must_understand = true,
+                "public" => r.public = true,
+                "private" => r.private = true,
+                "immutable" => r.immutable = true,
+                "max-age" => r.max_age = Some(seconds()?),
+                "s-maxage" => r.s_maxage = Some(seconds()?),
+                // without an argument, any stale response is accepted
+                "max-stale" => r.max_stale = Some(seconds().unwrap_or(u64::MAX)),
+                "min-fresh" => r.min_fresh = Some(seconds()?),
+                "stale-while-revalidate" => r.stale_while_revalidate = Some(seconds()?),
+                "stale-if-error" => r.stale_if_error = Some(seconds()?),
+                _ => r
+                    .extensions
+                    .push((name.to_owned(), argument.map(|x| x.into_owned()))),
+            }
+        }
+
+        Some(r)
+    }
+
+    fn encode(&self) -> String {
+        let flags = [
+            (self.no_cache, "no-cache"),
+            (self.no_store, "no-store"),
+            (self.no_transform, "no-transform"),
+            (self.only_if_cached, "only-if-cached"),
+            (self.must_revalidate, "must-revalidate"),
+            (self.proxy_revalidate, "proxy-revalidate"),
+            (self.must_understand, "must-understand"),
+            (self.public, "public"),
+            (self.private, "private"),
+            (self.immutable, "immutable"),
+        ];
+
+        let seconds = [
+            (self.max_age, "max-age"),
+            (self.s_maxage, "s-maxage"),
+            (self.max_stale, "max-stale"),
+            (self.min_fresh, "min-fresh"),
+            (self.stale_while_revalidate, "stale-while-revalidate"),
+            (self.stale_if_error, "stale-if-error"),
+        ];
+
+        let flags = flags
+            .into_iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| name.to_owned());
+
+        let seconds = seconds.into_iter().filter_map(|(x, name)| match x? {
+            u64::MAX if name == "max-stale" => Some(name.to_owned()),
+            x => Some(format!("{name}={x}")),
+        });
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|(name, argument)| match argument {
+                Some(argument) => format!("{}={}", name, quote(argument)),
+                None => name.clone(),
+            });
+
+        flags.chain(seconds).chain(extensions).join(", ")
+    }
+}
+
+#[test]
+fn cache_control() {
+    let cache_control = CacheControl::new().public().max_age(3600).immutable();
+
+    assert_eq!(cache_control.encode(), "public, immutable, max-age=3600");
+
+    let decoded =
+        CacheControl::decode(r#"No-Cache, max-age="60", max-stale, community="UCI""#).unwrap();
+
+    assert!(decoded.no_cache);
+    assert_eq!(decoded.max_age, Some(60));
+    assert_eq!(decoded.max_stale, Some(u64::MAX));
+    assert_eq!(
+        decoded.extensions,
+        [("community".to_owned(), Some("UCI".to_owned()))]
+    );
+    assert_eq!(
+        decoded.encode(),
+        "no-cache, max-age=60, max-stale, community=UCI"
+    );
+
+    assert_eq!(CacheControl::decode("max-age=soon"), None);
+}
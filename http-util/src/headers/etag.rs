@@ -0,0 +1,270 @@
+use std::{fmt::Display, str::FromStr};
+
+use http::header::{self, HeaderName};
+use itertools::Itertools;
+
+use super::{split_unquoted, Header};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid entity tag: {0}")]
+pub struct EntityTagError(String);
+
+/// `"xyzzy"` or `W/"xyzzy"`, RFC 9110 8.8.3
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// # Panics
+    ///
+    /// If the tag contains `"` or a character other than visible ASCII and obs-text,
+    /// use [`EntityTag::try_strong`] for a tag not known in advance
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self::try_strong(tag).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// # Panics
+    ///
+    /// If the tag contains `"` or a character other than visible ASCII and obs-text,
+    /// use [`EntityTag::try_weak`] for a tag not known in advance
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self::try_weak(tag).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_strong(tag: impl Into<String>) -> Result<Self, EntityTagError> {
+        Self::new(false, tag.into())
+    }
+
+    pub fn try_weak(tag: impl Into<String>) -> Result<Self, EntityTagError> {
+        Self::new(true, tag.into())
+    }
+
+    fn new(weak: bool, tag: String) -> Result<Self, EntityTagError> {
+        if !is_etagc(&tag) {
+            return Err(EntityTagError(tag));
+        }
+
+        Ok(Self { weak, tag })
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Without the quotes
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Both are strong and the same
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// The same regardless of weakness
+    pub fn weak_eq(&self, other: &EntityTag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+fn is_etagc(st: &str) -> bool {
+    st.bytes()
+        .all(|c| c == 0x21 || (0x23..=0x7E).contains(&c) || c >= 0x80)
+}
+
+impl FromStr for EntityTag {
+    type Err = EntityTagError;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        let st = st.trim();
+
+        let (weak, quoted) = match st.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, st),
+        };
+
+        let tag = quoted
+            .strip_prefix('"')
+            .and_then(|x| x.strip_suffix('"'))
+            .filter(|x| is_etagc(x))
+            .ok_or_else(|| EntityTagError(st.to_owned()))?;
+
+        Ok(Self {
+            weak,
+            tag: tag.to_owned(),
+        })
+    }
+}
+
+impl Display for EntityTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.weak {
+            write!(f, "W/")?;
+        }
+
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+/// `ETag: "xyzzy"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(pub EntityTag);
+
+impl ETag {
+    /// # Panics
+    ///
+    /// As [`EntityTag::strong`] does
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self(EntityTag::strong(tag))
+    }
+
+    /// # Panics
+    ///
+    /// As [`EntityTag::weak`] does
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self(EntityTag::weak(tag))
+    }
+
+    pub fn try_strong(tag: impl Into<String>) -> Result<Self, EntityTagError> {
+        EntityTag::try_strong(tag).map(Self)
+    }
+
+    pub fn try_weak(tag: impl Into<String>) -> Result<Self, EntityTagError> {
+        EntityTag::try_weak(tag).map(Self)
+    }
+}
+
+impl Header for ETag {
+    fn name() -> HeaderName {
+        header::ETAG
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        value.parse().ok().map(Self)
+    }
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+fn decode_tags(value: &str) -> Option<Vec<EntityTag>> {
+    split_unquoted(value, ',')
+        .into_iter()
+        .map(|x| x.parse().ok())
+        .collect()
+}
+
+/// `If-Match: "a", "b"` or `If-Match: *`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfMatch {
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+impl IfMatch {
+    /// By the strong comparison, RFC 9110 13.1.1
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|x| x.strong_eq(etag)),
+        }
+    }
+}
+
+impl Header for IfMatch {
+    fn name() -> HeaderName {
+        header::IF_MATCH
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        match value.trim() {
+            "*" => Some(Self::Any),
+            _ => decode_tags(value).map(Self::Tags),
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Any => "*".to_owned(),
+            Self::Tags(tags) => tags.iter().join(", "),
+        }
+    }
+}
+
+/// `If-None-Match: "a", W/"b"` or `If-None-Match: *`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfNoneMatch {
+    Any,
+    Tags(Vec<EntityTag>),
+}
+
+impl IfNoneMatch {
+    /// By the weak comparison, RFC 9110 13.1.2
+    pub fn matches(&self, etag: &EntityTag) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|x| x.weak_eq(etag)),
+        }
+    }
+}
+
+impl Header for IfNoneMatch {
+    fn name() -> HeaderName {
+        header::IF_NONE_MATCH
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        match value.trim() {
+            "*" => Some(Self::Any),
+            _ => decode_tags(value).map(Self::Tags),
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Any => "*".to_owned(),
+            Self::Tags(tags) => tags.iter().join(", "),
+        }
+    }
+}
+
+#[test]
+fn etag() {
+    let etag = ETag::decode(r#"W/"a,b""#).unwrap();
+
+    assert!(etag.0.is_weak());
+    assert_eq!(etag.0.tag(), "a,b");
+    assert_eq!(etag.encode(), r#"W/"a,b""#);
+    assert_eq!(ETag::decode("abc"), None);
+    assert_eq!(ETag::decode(r#""a"b""#), None);
+
+    let strong = EntityTag::strong("1");
+    let weak = EntityTag::weak("1");
+
+    assert!(strong.strong_eq(&strong));
+    assert!(!strong.strong_eq(&weak));
+    assert!(strong.weak_eq(&weak));
+
+    let if_none_match = IfNoneMatch::decode(r#""0", W/"1""#).unwrap();
+
+    assert!(if_none_match.matches(&strong));
+    assert!(!if_none_match.matches(&EntityTag::strong("2")));
+    assert!(IfNoneMatch::decode("*").unwrap().matches(&strong));
+    assert_eq!(IfNoneMatch::decode(r#""0", x"#), None);
+
+    let if_match = IfMatch::decode(r#"W/"0", "1""#).unwrap();
+
+    assert!(if_match.matches(&strong));
+    assert!(!if_match.matches(&weak));
+    assert!(!if_match.matches(&EntityTag::strong("0")));
+
+    assert_eq!(ETag::try_strong("a-b").unwrap().encode(), r#""a-b""#);
+    assert!(ETag::try_strong("a b").is_err());
+    assert_eq!(ETag::try_weak("사진").unwrap().0, EntityTag::weak("사진"));
+    assert!(EntityTag::try_strong(r#"a"b"#).is_err());
+    assert!(EntityTag::try_weak("a\nb").is_err());
+    assert!(ETag::try_strong("\u{7f}").is_err());
+}
@@ -0,0 +1,137 @@
+use http::header::{self, HeaderName};
+use itertools::Itertools;
+
+use super::{quote, split_unquoted, unquote, Header};
+
+/// A proxy hop of `Forwarded`, RFC 7239
+///
+/// Node values are kept as sent, `192.0.2.43`, `[2001:db8:cafe::17]:4711`, `unknown` or
+/// `_hidden`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ForwardedElement {
+    pub for_: Option<String>,
+    pub by: Option<String>,
+    pub host: Option<String>,
+    pub proto: Option<String>,
+}
+
+/// `Forwarded: for=192.0.2.60;proto=http;by=203.0.113.43, for="[2001:db8:cafe::17]"`
+///
+/// The client first, then the proxies in order
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Forwarded(pub Vec<ForwardedElement>);
+
+impl Forwarded {
+    /// `for` of the first hop
+    pub fn client(&self) -> Option<&str> {
+        self.0.first()?.for_.as_deref()
+    }
+}
+
+impl Header for Forwarded {
+    fn name() -> HeaderName {
+        header::FORWARDED
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let mut elements = Vec::new();
+
+        for element in split_unquoted(value, ',') {
+            let mut r = ForwardedElement::default();
+
+            for pair in split_unquoted(element, ';') {
+                let (name, value) = pair.split_once('=')?;
+                let value = Some(unquote(value).into_owned());
+
+                // parameter names are case-insensitive, unknown ones are ignored
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "for" => r.for_ = value,
+                    "by" => r.by = value,
+                    "host" => r.host = value,
+                    "proto" => r.proto = value,
+                    _ => {}
+                }
+            }
+
+            elements.push(r);
+        }
+
+        Some(Self(elements))
+    }
+
+    fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| {
+                [
+                    ("for", &x.for_),
+                    ("by", &x.by),
+                    ("host", &x.host),
+                    ("proto", &x.proto),
+                ]
+                .into_iter()
+                .filter_map(|(name, value)| Some(format!("{}={}", name, quote(value.as_deref()?))))
+                .join(";")
+            })
+            .join(", ")
+    }
+}
+
+/// `X-Forwarded-For: client, proxy1, proxy2`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct XForwardedFor(pub Vec<String>);
+
+impl XForwardedFor {
+    /// The left-most address, which is as trustworthy as the proxies in between
+    pub fn client(&self) -> Option<&str> {
+        self.0.first().map(String::as_str)
+    }
+}
+
+impl Header for XForwardedFor {
+    fn name() -> HeaderName {
+        HeaderName::from_static("x-forwarded-for")
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let addresses = value
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+
+        (!addresses.is_empty()).then_some(Self(addresses))
+    }
+
+    fn encode(&self) -> String {
+        self.0.join(", ")
+    }
+}
+
+#[test]
+fn forwarded() {
+    let forwarded = Forwarded::decode(
+        r#"For="[2001:db8:cafe::17]:4711";proto=https, for=192.0.2.60;by=203.0.113.43;secret=x"#,
+    )
+    .unwrap();
+
+    assert_eq!(forwarded.client(), Some("[2001:db8:cafe::17]:4711"));
+    assert_eq!(forwarded.0[0].proto.as_deref(), Some("https"));
+    assert_eq!(forwarded.0[1].by.as_deref(), Some("203.0.113.43"));
+    assert_eq!(
+        forwarded.encode(),
+        r#"for="[2001:db8:cafe::17]:4711";proto=https, for=192.0.2.60;by=203.0.113.43"#
+    );
+    assert_eq!(Forwarded::decode("for"), None);
+
+    let x_forwarded_for =
+        XForwardedFor::decode("203.0.113.195, 70.41.3.18,150.172.238.178").unwrap();
+
+    assert_eq!(x_forwarded_for.client(), Some("203.0.113.195"));
+    assert_eq!(
+        x_forwarded_for.encode(),
+        "203.0.113.195, 70.41.3.18, 150.172.238.178"
+    );
+    assert_eq!(XForwardedFor::decode(" , "), None);
+}
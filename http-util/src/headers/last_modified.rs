@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use http::header::{self, HeaderName};
+
+use super::{fmt_http_date, parse_http_date, Header};
+
+macro_rules! date_header {
+    ($(#[$doc:meta])* $ty:ident, $name:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $ty(pub DateTime<Utc>);
+
+        impl Header for $ty {
+            fn name() -> HeaderName {
+                $name
+            }
+
+            fn decode(value: &str) -> Option<Self> {
+                parse_http_date(value).map(Self)
+            }
+
+            fn encode(&self) -> String {
+                fmt_http_date(&self.0)
+            }
+        }
+    };
+}
+
+date_header!(
+    /// `Last-Modified: Tue, 15 Nov 1994 12:45:26 GMT`
+    LastModified,
+    header::LAST_MODIFIED
+);
+
+date_header!(
+    /// `If-Modified-Since: Sat, 29 Oct 1994 19:43:31 GMT`
+    IfModifiedSince,
+    header::IF_MODIFIED_SINCE
+);
+
+date_header!(
+    /// `If-Unmodified-Since: Sat, 29 Oct 1994 19:43:31 GMT`
+    IfUnmodifiedSince,
+    header::IF_UNMODIFIED_SINCE
+);
+
+#[test]
+fn date_headers() {
+    let last_modified = LastModified::decode("Tue, 15 Nov 1994 12:45:26 GMT").unwrap();
+
+    assert_eq!(last_modified.0.timestamp(), 784903526);
+    assert_eq!(last_modified.encode(), "Tue, 15 Nov 1994 12:45:26 GMT");
+    assert_eq!(
+        IfModifiedSince(last_modified.0).encode(),
+        "Tue, 15 Nov 1994 12:45:26 GMT"
+    );
+    assert_eq!(
+        IfModifiedSince::decode("Tuesday, 15-Nov-94 12:45:26 GMT"),
+        Some(IfModifiedSince(last_modified.0))
+    );
+    assert_eq!(
+        IfModifiedSince::decode("Tue Nov 15 12:45:26 1994"),
+        Some(IfModifiedSince(last_modified.0))
+    );
+    assert_eq!(IfUnmodifiedSince::decode("yesterday"), None);
+}
@@ -0,0 +1,343 @@
+use std::{fmt::Display, str::FromStr};
+
+use http::header::{self, HeaderName};
+use itertools::Itertools;
+
+use super::{is_token, quote, split_unquoted, unquote, Header};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid media type: {0}")]
+pub struct MediaTypeError(String);
+
+/// `type/subtype; name=value`, RFC 9110 8.3.1
+///
+/// Type, subtype and parameter names are lowercased, parameter values are kept as they are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    pub fn new(type_: &str, subtype: &str) -> Self {
+        Self {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Replaces the parameter of the same name
+    pub fn param(mut self, name: &str, value: impl Into<String>) -> Self {
+        let name = name.to_ascii_lowercase();
+        let value = value.into();
+
+        match self.params.iter_mut().find(|(k, _)| *k == name) {
+            Some((_, v)) => *v = value,
+            None => self.params.push((name, value)),
+        }
+
+        self
+    }
+
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// `type/subtype` without parameters
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.get_param("charset")
+    }
+
+    /// As a media range, `*/*`, `type/*` or `type/subtype` with the parameters a subset of
+    /// the other's
+    pub fn matches(&self, other: &MediaType) -> bool {
+        let type_matches = self.type_ == "*" || self.type_ == other.type_;
+        let subtype_matches = self.subtype == "*" || self.subtype == other.subtype;
+
+        type_matches
+            && subtype_matches
+            && self.params.iter().all(|(k, v)| {
+                other
+                    .get_param(k)
+                    .is_some_and(|x| x.eq_ignore_ascii_case(v))
+            })
+    }
+
+    /// `*/*` < `type/*` < `type/subtype` < `type/subtype;params`
+    pub fn specificity(&self) -> u8 {
+        match (self.type_.as_str(), self.subtype.as_str()) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ if self.params.is_empty() => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = MediaTypeError;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        let err = || MediaTypeError(st.to_owned());
+
+        let mut parts = split_unquoted(st, ';').into_iter();
+
+        let (type_, subtype) = parts
+            .next()
+            .and_then(|x| x.split_once('/'))
+            .ok_or_else(err)?;
+        let (type_, subtype) = (type_.trim(), subtype.trim());
+
+        if !is_token(type_) || !is_token(subtype) {
+            return Err(err());
+        }
+
+        let mut media_type = Self::new(type_, subtype);
+
+        for param in parts {
+            let (name, value) = param.split_once('=').ok_or_else(err)?;
+            let name = name.trim();
+
+            if !is_token(name) {
+                return Err(err());
+            }
+
+            media_type = media_type.param(name, unquote(value));
+        }
+
+        Ok(media_type)
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+
+        for (name, value) in &self.params {
+            write!(f, "; {}={}", name, quote(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `Content-Type: text/html; charset=utf-8`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType(pub MediaType);
+
+impl ContentType {
+    pub fn json() -> Self {
+        Self(MediaType::new("application", "json"))
+    }
+
+    pub fn text_utf8() -> Self {
+        Self(MediaType::new("text", "plain").param("charset", "utf-8"))
+    }
+}
+
+impl Header for ContentType {
+    fn name() -> HeaderName {
+        header::CONTENT_TYPE
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        value.parse().ok().map(Self)
+    }
+
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// A media range of `Accept` with its weight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualityItem {
+    pub media_range: MediaType,
+    /// `q` in thousandths, 0 to 1000
+    pub quality: u16,
+}
+
+/// `Accept: text/html, application/json;q=0.9, */*;q=0.1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accept(pub Vec<QualityItem>);
+
+impl Accept {
+    /// Higher quality first, then more specific, otherwise in the order sent
+    pub fn sorted(&self) -> Vec<&QualityItem> {
+        self.0
+            .iter()
+            .sorted_by(|a, b| {
+                b.quality.cmp(&a.quality).then_with(|| {
+                    b.media_range
+                        .specificity()
+                        .cmp(&a.media_range.specificity())
+                })
+            })
+            .collect()
+    }
+
+    /// Quality of the media type by the most specific matching range, 0 if none matches
+    pub fn quality_of(&self, media_type: &MediaType) -> u16 {
+        self.0
+            .iter()
+            .filter(|x| x.media_range.matches(media_type))
+            .max_by_key(|x| x.media_range.specificity())
+            .map_or(0, |x| x.quality)
+    }
+}
+
+/// `0`, `0.5`, `1.000`
+fn parse_quality(st: &str) -> Option<u16> {
+    let (int, frac) = st.trim().split_once('.').unwrap_or((st.trim(), ""));
+
+    if frac.len() > 3 || !frac.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let frac = format!("{frac:0<3}").parse::<u16>().ok()?;
+
+    match int {
+        "0" => Some(frac),
+        "1" if frac == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+fn fmt_quality(quality: u16) -> String {
+    match quality {
+        0 => "0".to_owned(),
+        1000.. => "1".to_owned(),
+        _ => format!("0.{quality:03}").trim_end_matches('0').to_owned(),
+    }
+}
+
+impl Header for Accept {
+    fn name() -> HeaderName {
+        header::ACCEPT
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let mut items = Vec::new();
+
+        for item in split_unquoted(value, ',') {
+            let mut media_range = item.parse::<MediaType>().ok()?;
+            let mut quality = 1000;
+
+            // `q` separates the media type parameters from the accept extensions
+            if let Some(i) = media_range.params.iter().position(|(k, _)| k == "q") {
+                quality = parse_quality(&media_range.params[i].1)?;
+                media_range.params.truncate(i);
+            }
+
+            items.push(QualityItem {
+                media_range,
+                quality,
+            });
+        }
+
+        Some(Self(items))
+    }
+
+    fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| match x.quality {
+                1000.. => x.media_range.to_string(),
+                q => format!("{};q={}", x.media_range, fmt_quality(q)),
+            })
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Accept, ContentType, MediaType};
+    use crate::headers::Header;
+
+    #[test]
+    fn test_media_type() {
+        let media_type: MediaType = r#"Text/HTML; Charset="utf-8"; a="b c""#.parse().unwrap();
+
+        assert_eq!(media_type.essence(), "text/html");
+        assert_eq!(media_type.charset(), Some("utf-8"));
+        assert_eq!(
+            media_type.to_string(),
+            r#"text/html; charset=utf-8; a="b c""#
+        );
+
+        assert!("text".parse::<MediaType>().is_err());
+        assert!("text/html; a".parse::<MediaType>().is_err());
+
+        let range: MediaType = "text/*".parse().unwrap();
+
+        assert!(range.matches(&media_type));
+        assert!(!range.matches(&MediaType::new("application", "json")));
+        assert!(MediaType::new("text", "html")
+            .param("charset", "UTF-8")
+            .matches(&media_type));
+        assert!(!MediaType::new("text", "html")
+            .param("level", "1")
+            .matches(&media_type));
+
+        assert_eq!(
+            ContentType::decode("application/json").unwrap(),
+            ContentType::json()
+        );
+        assert_eq!(
+            ContentType::text_utf8().encode(),
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn test_accept() {
+        let accept =
+            Accept::decode("text/*;q=0.3, text/html;q=0.7, text/html;level=1, */*;q=0.5").unwrap();
+
+        assert_eq!(
+            accept
+                .sorted()
+                .iter()
+                .map(|x| x.media_range.to_string())
+                .collect::<Vec<_>>(),
+            ["text/html; level=1", "text/html", "*/*", "text/*"]
+        );
+
+        let html = MediaType::new("text", "html");
+
+        assert_eq!(accept.quality_of(&html), 700);
+        assert_eq!(accept.quality_of(&html.clone().param("level", "1")), 1000);
+        assert_eq!(accept.quality_of(&MediaType::new("text", "plain")), 300);
+        assert_eq!(accept.quality_of(&MediaType::new("image", "png")), 500);
+
+        assert_eq!(
+            accept.encode(),
+            "text/*;q=0.3, text/html;q=0.7, text/html; level=1, */*;q=0.5"
+        );
+
+        assert_eq!(Accept::decode("*/*;q=0").unwrap().encode(), "*/*;q=0");
+        assert_eq!(Accept::decode("*/*;q=1.5"), None);
+        assert_eq!(Accept::decode("*/*;q=0.1234"), None);
+    }
+}
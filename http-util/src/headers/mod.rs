@@ -0,0 +1,324 @@
+mod authorization;
+mod cache_control;
+mod etag;
+mod forwarded;
+mod last_modified;
+mod media_type;
+mod range;
+mod retry_after;
+
+pub use authorization::*;
+pub use cache_control::*;
+pub use etag::*;
+pub use forwarded::*;
+pub use last_modified::*;
+pub use media_type::*;
+pub use range::*;
+pub use retry_after::*;
+
+use std::borrow::Cow;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use http::{header::HeaderName, HeaderMap, HeaderValue, StatusCode};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum HeaderError {
+    #[error("Missing header: {0}")]
+    Missing(HeaderName),
+    #[error("Invalid header: {0}")]
+    Invalid(HeaderName),
+}
+
+impl HeaderError {
+    /// 400
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Typed encode and decode of a header
+pub trait Header: Sized {
+    fn name() -> HeaderName;
+
+    /// Values of every line of the header are joined with `, `
+    fn decode(value: &str) -> Option<Self>;
+
+    fn encode(&self) -> String;
+
+    fn to_header_value(&self) -> Result<HeaderValue, HeaderError> {
+        HeaderValue::from_str(&self.encode()).map_err(|_| HeaderError::Invalid(Self::name()))
+    }
+
+    fn from_headers(headers: &HeaderMap) -> Result<Self, HeaderError> {
+        let values = headers
+            .get_all(Self::name())
+            .iter()
+            .map(|x| x.to_str())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| HeaderError::Invalid(Self::name()))?;
+
+        if values.is_empty() {
+            return Err(HeaderError::Missing(Self::name()));
+        }
+
+        Self::decode(&values.join(", ")).ok_or_else(|| HeaderError::Invalid(Self::name()))
+    }
+}
+
+pub trait HeaderMapExt {
+    fn typed_get<H: Header>(&self) -> Result<H, HeaderError>;
+
+    /// Replaces the existing values
+    fn typed_insert<H: Header>(&mut self, header: &H) -> Result<(), HeaderError>;
+}
+
+impl HeaderMapExt for HeaderMap {
+    fn typed_get<H: Header>(&self) -> Result<H, HeaderError> {
+        H::from_headers(self)
+    }
+
+    fn typed_insert<H: Header>(&mut self, header: &H) -> Result<(), HeaderError> {
+        self.insert(H::name(), header.to_header_value()?);
+
+        Ok(())
+    }
+}
+
+/// Extracts a typed header from the request
+///
+/// `Option<TypedHeader<H>>` for an optional one
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedHeader<H>(pub H);
+
+#[cfg(feature = "server")]
+#[async_trait::async_trait]
+impl<'a, H> crate::FromRequest<'a> for TypedHeader<H>
+where
+    H: Header + Send,
+{
+    type Parameter = ();
+    type Error = HeaderError;
+
+    async fn from_request(
+        _: Self::Parameter,
+        request: &'a mut http::Request<hyper::Body>,
+    ) -> Result<Self, Self::Error> {
+        H::from_headers(request.headers()).map(TypedHeader)
+    }
+}
+
+/// IMF-fixdate, `Sun, 06 Nov 1994 08:49:37 GMT`
+pub(crate) const IMF_FIXDATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+pub(crate) fn fmt_http_date(date: &DateTime<Utc>) -> String {
+    date.format(IMF_FIXDATE).to_string()
+}
+
+/// IMF-fixdate, RFC 850 `Sunday, 06-Nov-94 08:49:37 GMT` or asctime `Sun Nov  6 08:49:37 1994`,
+/// RFC 9110 5.6.7
+pub(crate) fn parse_http_date(st: &str) -> Option<DateTime<Utc>> {
+    let st = st.trim();
+
+    [
+        IMF_FIXDATE,
+        "%A, %d-%b-%y %H:%M:%S GMT",
+        "%a %b %e %H:%M:%S %Y",
+    ]
+    .into_iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(st, format).ok())
+    .map(|x| x.and_utc())
+}
+
+/// tchar of RFC 9110
+pub(crate) fn is_token(st: &str) -> bool {
+    !st.is_empty()
+        && st
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c))
+}
+
+/// Splits by the separator outside of quoted strings, trims and skips empty items
+pub(crate) fn split_unquoted(st: &str, separator: char) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in st.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                items.push(&st[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    items.push(&st[start..]);
+
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Strips the quotes and backslash escapes of a quoted string
+pub(crate) fn unquote(st: &str) -> Cow<'_, str> {
+    let st = st.trim();
+
+    match st.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(inner) if inner.contains('\\') => {
+            let mut r = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => r.extend(chars.next()),
+                    c => r.push(c),
+                }
+            }
+
+            Cow::Owned(r)
+        }
+        Some(inner) => Cow::Borrowed(inner),
+        None => Cow::Borrowed(st),
+    }
+}
+
+/// As it is if a token, otherwise a quoted string
+pub(crate) fn quote(st: &str) -> Cow<'_, str> {
+    if is_token(st) {
+        Cow::Borrowed(st)
+    } else {
+        Cow::Owned(format!(
+            "\"{}\"",
+            st.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, HeaderMap};
+
+    use super::{
+        fmt_http_date, parse_http_date, quote, split_unquoted, unquote, ETag, Header, HeaderError,
+        HeaderMapExt, IfNoneMatch,
+    };
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(
+            split_unquoted(r#"a, "b, c", "d\", e","#, ','),
+            [r#"a"#, r#""b, c""#, r#""d\", e""#]
+        );
+        assert_eq!(unquote(r#""a\"b""#), r#"a"b"#);
+        assert_eq!(quote("abc"), "abc");
+        assert_eq!(quote("a b\""), r#""a b\"""#);
+    }
+
+    #[test]
+    fn test_http_date() {
+        for st in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            " Sun, 06 Nov 1994 08:49:37 GMT ",
+        ] {
+            let date = parse_http_date(st).unwrap();
+
+            assert_eq!(date.timestamp(), 784111777, "{st}");
+            assert_eq!(fmt_http_date(&date), "Sun, 06 Nov 1994 08:49:37 GMT");
+        }
+
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT").map(|x| x.timestamp()),
+            Some(0)
+        );
+
+        for st in [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 +0900",
+            "Mon, 06 Nov 1994 08:49:37 GMT",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "1994-11-06T08:49:37Z",
+        ] {
+            assert_eq!(parse_http_date(st), None, "{st}");
+        }
+    }
+
+    #[test]
+    fn test_header_map_ext() {
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(
+            headers.typed_get::<ETag>(),
+            Err(HeaderError::Missing(header::ETAG))
+        );
+
+        headers.append(header::IF_NONE_MATCH, r#""a""#.parse().unwrap());
+        headers.append(header::IF_NONE_MATCH, r#"W/"b""#.parse().unwrap());
+
+        let if_none_match = headers.typed_get::<IfNoneMatch>().unwrap();
+
+        assert_eq!(if_none_match.encode(), r#""a", W/"b""#);
+
+        headers.typed_insert(&ETag::strong("c")).unwrap();
+
+        assert_eq!(headers[header::ETAG], r#""c""#);
+        assert_eq!(
+            headers.typed_get::<ETag>().map(|x| x.0.tag().to_owned()),
+            Ok("c".to_owned())
+        );
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_typed_header_extract_and_set() {
+        use http::{Request, Response};
+        use hyper::Body;
+
+        use super::{Authorization, CacheControl, TypedHeader};
+        use crate::{FromRequest, SetResponse};
+
+        let mut request = Request::get("/")
+            .header(header::AUTHORIZATION, "Bearer abc")
+            .body(Body::empty())
+            .unwrap();
+
+        let TypedHeader(authorization) =
+            TypedHeader::<Authorization>::from_request((), &mut request)
+                .await
+                .unwrap();
+
+        assert_eq!(authorization.token(), Some("abc"));
+
+        let missing = Option::<TypedHeader<ETag>>::from_request((), &mut request)
+            .await
+            .unwrap();
+
+        assert_eq!(missing, None);
+
+        let mut response = Response::new(());
+
+        response
+            .set_header(header::CACHE_CONTROL, "no-store")
+            .unwrap();
+        response
+            .set_typed_header(&CacheControl::new().private().max_age(60))
+            .unwrap();
+
+        assert_eq!(
+            response
+                .headers()
+                .get_all(header::CACHE_CONTROL)
+                .iter()
+                .collect::<Vec<_>>(),
+            ["private, max-age=60"]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use http::header::{self, HeaderName};
+use itertools::Itertools;
+
+use super::Header;
+
+/// A range of `Range: bytes=`, RFC 9110 14.1.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `0-499`, inclusive
+    FromTo(u64, u64),
+    /// `500-`
+    From(u64),
+    /// `-500`, the last 500 bytes
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Inclusive `(first, last)` within the representation, `None` if unsatisfiable
+    pub fn to_satisfiable(&self, len: u64) -> Option<(u64, u64)> {
+        match *self {
+            Self::FromTo(first, _) | Self::From(first) if first >= len => None,
+            Self::FromTo(first, last) => Some((first, last.min(len - 1))),
+            Self::From(first) => Some((first, len - 1)),
+            Self::Suffix(0) => None,
+            Self::Suffix(_) if len == 0 => None,
+            Self::Suffix(suffix) => Some((len.saturating_sub(suffix), len - 1)),
+        }
+    }
+}
+
+/// `Range: bytes=0-499, -500`
+///
+/// Units other than `bytes` are not decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range(pub Vec<ByteRange>);
+
+impl Range {
+    pub fn bytes(first: u64, last: Option<u64>) -> Self {
+        match last {
+            Some(last) => Self(vec![ByteRange::FromTo(first, last)]),
+            None => Self(vec![ByteRange::From(first)]),
+        }
+    }
+
+    /// Satisfiable ranges only, empty means 416
+    pub fn to_satisfiable(&self, len: u64) -> Vec<(u64, u64)> {
+        self.0
+            .iter()
+            .filter_map(|x| x.to_satisfiable(len))
+            .collect()
+    }
+}
+
+impl Header for Range {
+    fn name() -> HeaderName {
+        header::RANGE
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let (unit, ranges) = value.trim().split_once('=')?;
+
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let ranges = ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|range| {
+                let (first, last) = range.split_once('-')?;
+                let (first, last) = (first.trim(), last.trim());
+
+                match (first.is_empty(), last.is_empty()) {
+                    (true, false) => Some(ByteRange::Suffix(last.parse().ok()?)),
+                    (false, true) => Some(ByteRange::From(first.parse().ok()?)),
+                    (false, false) => {
+                        let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+
+                        (first <= last).then_some(ByteRange::FromTo(first, last))
+                    }
+                    (true, true) => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        (!ranges.is_empty()).then_some(Self(ranges))
+    }
+
+    fn encode(&self) -> String {
+        let ranges = self.0.iter().map(|x| match x {
+            ByteRange::FromTo(first, last) => format!("{first}-{last}"),
+            ByteRange::From(first) => format!("{first}-"),
+            ByteRange::Suffix(suffix) => format!("-{suffix}"),
+        });
+
+        format!("bytes={}", ranges.format(", "))
+    }
+}
+
+/// `Content-Range: bytes 0-499/1234`, `bytes 0-499/*` or `bytes */1234`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// Inclusive, absent in a 416 response
+    pub range: Option<(u64, u64)>,
+    /// Absent if unknown
+    pub complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    pub fn bytes(first: u64, last: u64, complete_length: Option<u64>) -> Self {
+        Self {
+            range: Some((first, last)),
+            complete_length,
+        }
+    }
+
+    /// Of a 416 response
+    pub fn unsatisfied(complete_length: u64) -> Self {
+        Self {
+            range: None,
+            complete_length: Some(complete_length),
+        }
+    }
+}
+
+impl Header for ContentRange {
+    fn name() -> HeaderName {
+        header::CONTENT_RANGE
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let (unit, rest) = value.trim().split_once(' ')?;
+
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+
+        let (range, complete_length) = rest.trim().split_once('/')?;
+
+        let complete_length = match complete_length {
+            "*" => None,
+            x => Some(x.parse().ok()?),
+        };
+
+        let range = match range {
+            "*" => None,
+            x => {
+                let (first, last) = x.split_once('-')?;
+
+                Some((first.parse().ok()?, last.parse().ok()?))
+            }
+        };
+
+        match (range, complete_length) {
+            (None, None) => None,
+            (Some((first, last)), _) if first > last => None,
+            (Some((_, last)), Some(len)) if last >= len => None,
+            _ => Some(Self {
+                range,
+                complete_length,
+            }),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let range = match self.range {
+            Some((first, last)) => format!("{first}-{last}"),
+            None => "*".to_owned(),
+        };
+
+        let complete_length = match self.complete_length {
+            Some(len) => len.to_string(),
+            None => "*".to_owned(),
+        };
+
+        format!("bytes {range}/{complete_length}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteRange, ContentRange, Range};
+    use crate::headers::Header;
+
+    #[test]
+    fn test_range() {
+        let range = Range::decode("bytes=0-499, 500-, -200").unwrap();
+
+        assert_eq!(
+            range.0,
+            [
+                ByteRange::FromTo(0, 499),
+                ByteRange::From(500),
+                ByteRange::Suffix(200)
+            ]
+        );
+        assert_eq!(range.encode(), "bytes=0-499, 500-, -200");
+        assert_eq!(range.to_satisfiable(300), [(0, 299), (100, 299)]);
+        assert_eq!(range.to_satisfiable(0), []);
+
+        assert_eq!(Range::decode("bytes=5-1"), None);
+        assert_eq!(Range::decode("bytes=-"), None);
+        assert_eq!(Range::decode("items=0-1"), None);
+        assert_eq!(Range::bytes(10, None).encode(), "bytes=10-");
+    }
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(
+            ContentRange::decode("bytes 0-499/1234").unwrap(),
+            ContentRange::bytes(0, 499, Some(1234))
+        );
+        assert_eq!(ContentRange::bytes(0, 499, None).encode(), "bytes 0-499/*");
+        assert_eq!(ContentRange::unsatisfied(1234).encode(), "bytes */1234");
+        assert_eq!(
+            ContentRange::decode("bytes */1234").unwrap(),
+            ContentRange::unsatisfied(1234)
+        );
+        assert_eq!(ContentRange::decode("bytes 0-1234/1234"), None);
+        assert_eq!(ContentRange::decode("bytes */*"), None);
+    }
+}
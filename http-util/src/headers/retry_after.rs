@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use http::header::{self, HeaderName};
+
+use super::{fmt_http_date, parse_http_date, Header};
+
+/// `Retry-After: 120` or `Retry-After: Fri, 31 Dec 1999 23:59:59 GMT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    /// Seconds
+    Delay(u64),
+    Date(DateTime<Utc>),
+}
+
+impl RetryAfter {
+    /// Seconds from now, 0 if the date has passed
+    pub fn delay_from(&self, now: DateTime<Utc>) -> u64 {
+        match self {
+            Self::Delay(seconds) => *seconds,
+            Self::Date(date) => (*date - now).num_seconds().max(0) as u64,
+        }
+    }
+}
+
+impl Header for RetryAfter {
+    fn name() -> HeaderName {
+        header::RETRY_AFTER
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        match value.parse() {
+            Ok(seconds) => Some(Self::Delay(seconds)),
+            Err(_) => parse_http_date(value).map(Self::Date),
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Self::Delay(seconds) => seconds.to_string(),
+            Self::Date(date) => fmt_http_date(date),
+        }
+    }
+}
+
+#[test]
+fn retry_after() {
+    assert_eq!(RetryAfter::decode("120"), Some(RetryAfter::Delay(120)));
+
+    let date = RetryAfter::decode("Fri, 31 Dec 1999 23:59:59 GMT").unwrap();
+
+    assert_eq!(date.encode(), "Fri, 31 Dec 1999 23:59:59 GMT");
+
+    let RetryAfter::Date(at) = date else {
+        panic!("not a date");
+    };
+
+    assert_eq!(date.delay_from(at - chrono::Duration::seconds(30)), 30);
+    assert_eq!(date.delay_from(at + chrono::Duration::seconds(30)), 0);
+    assert_eq!(RetryAfter::decode("-1"), None);
+    assert_eq!(RetryAfter::decode("soon"), None);
+}
@@ -0,0 +1,183 @@
+use http::{header, HeaderMap, HeaderValue, Response, StatusCode};
+use hyper::Body;
+use serde::Serialize;
+
+use crate::{SetCookie, SetResponse};
+
+/// Turns a return value of a handler into a response
+pub trait IntoResponse {
+    fn into_response(self) -> Response<Body>;
+}
+
+impl IntoResponse for Response<Body> {
+    fn into_response(self) -> Response<Body> {
+        self
+    }
+}
+
+impl IntoResponse for () {
+    fn into_response(self) -> Response<Body> {
+        Response::new(Body::empty())
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response<Body> {
+        with_content_type(self.into(), "text/plain; charset=utf-8")
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response<Body> {
+        with_content_type(self.into(), "text/plain; charset=utf-8")
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response<Body> {
+        with_content_type(self.into(), "application/octet-stream")
+    }
+}
+
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Response<Body> {
+        let mut response = ().into_response();
+
+        *response.status_mut() = self;
+
+        response
+    }
+}
+
+impl<T> IntoResponse for (StatusCode, T)
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response<Body> {
+        let (status, x) = self;
+        let mut response = x.into_response();
+
+        *response.status_mut() = status;
+
+        response
+    }
+}
+
+/// Headers of the map replace the ones set by `T`
+impl<T> IntoResponse for (StatusCode, HeaderMap, T)
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response<Body> {
+        let (status, headers, x) = self;
+        let mut response = (status, x).into_response();
+
+        response.headers_mut().extend(headers);
+
+        response
+    }
+}
+
+/// Empty response with `Set-Cookie` headers
+impl IntoResponse for SetCookie {
+    fn into_response(self) -> Response<Body> {
+        (self, ()).into_response()
+    }
+}
+
+impl<T> IntoResponse for (SetCookie, T)
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response<Body> {
+        let (set_cookie, x) = self;
+        let mut response = x.into_response();
+
+        response.set_headers(set_cookie.iter());
+
+        response
+    }
+}
+
+impl<T, E> IntoResponse for Result<T, E>
+where
+    T: IntoResponse,
+    E: IntoResponse,
+{
+    fn into_response(self) -> Response<Body> {
+        match self {
+            Ok(x) => x.into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+/// Serializes into `application/json`, responds 500 if it fails
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T> IntoResponse for Json<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response<Body> {
+        match serde_json::to_vec(&self.0) {
+            Ok(buf) => with_content_type(buf.into(), "application/json"),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+fn with_content_type(body: Body, content_type: &'static str) -> Response<Body> {
+    let mut response = Response::new(body);
+
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, HeaderMap, StatusCode};
+    use hyper::body::to_bytes;
+
+    use super::{IntoResponse, Json};
+    use crate::{SetCookie, SetCookieOptions};
+
+    #[tokio::test]
+    async fn test_into_response() {
+        let response = (StatusCode::CREATED, "created").into_response();
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(to_bytes(response.into_body()).await.unwrap(), "created");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "text/csv".parse().unwrap());
+
+        let response = (StatusCode::OK, headers, b"a,b".to_vec()).into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+
+        let r: Result<Json<Vec<u32>>, StatusCode> = Ok(Json(vec![1, 2]));
+        let response = r.into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(to_bytes(response.into_body()).await.unwrap(), "[1,2]");
+
+        let r: Result<Json<Vec<u32>>, StatusCode> = Err(StatusCode::NOT_FOUND);
+
+        assert_eq!(r.into_response().status(), StatusCode::NOT_FOUND);
+
+        let set_cookie = SetCookie::new().set("a", "b", SetCookieOptions::new().path("/"));
+        let response = (set_cookie, String::from("hello")).into_response();
+
+        assert_eq!(response.headers()[header::SET_COOKIE], "a=b; Path=/");
+        assert_eq!(to_bytes(response.into_body()).await.unwrap(), "hello");
+    }
+}
@@ -1,33 +1,72 @@
 pub mod cookie;
+#[cfg(feature = "cookie-jar")]
+pub mod cookie_jar;
+pub mod cookie_store;
+pub mod headers;
 pub mod set_cookie;
 pub mod url;
 
 #[cfg(feature = "server")]
 pub mod body_parser;
 #[cfg(feature = "server")]
+pub mod conditional;
+#[cfg(feature = "server")]
 pub mod from_request;
 #[cfg(feature = "server")]
 pub mod header;
 #[cfg(feature = "server")]
+pub mod into_response;
+#[cfg(feature = "server")]
 pub mod multipart;
 #[cfg(feature = "server")]
+pub mod negotiation;
+#[cfg(feature = "server")]
+pub mod problem_details;
+#[cfg(feature = "server")]
 pub mod read_chunks;
 #[cfg(feature = "server")]
 pub mod response;
 
 pub use cookie::*;
+#[cfg(feature = "cookie-jar")]
+pub use cookie_jar::*;
+pub use cookie_store::*;
+pub use headers::*;
 pub use set_cookie::*;
 pub use url::*;
 
+// `Error`s of each module are not re-exported, use `body_parser::Error` and so on
 #[cfg(feature = "server")]
+#[allow(ambiguous_glob_reexports)]
 pub use body_parser::*;
 #[cfg(feature = "server")]
+pub use conditional::*;
+#[cfg(feature = "server")]
 pub use from_request::*;
 #[cfg(feature = "server")]
 pub use header::*;
+#[cfg(feature = "derive")]
+pub use http_util_derive::FromRequest;
 #[cfg(feature = "server")]
+pub use into_response::*;
+#[cfg(feature = "server")]
+#[allow(ambiguous_glob_reexports)]
 pub use multipart::*;
 #[cfg(feature = "server")]
+pub use negotiation::*;
+#[cfg(feature = "server")]
+pub use problem_details::*;
+#[cfg(feature = "server")]
+#[allow(ambiguous_glob_reexports)]
 pub use read_chunks::*;
 #[cfg(feature = "server")]
 pub use response::*;
+
+/// Used by `#[derive(FromRequest)]`
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use http;
+    pub use hyper;
+}
@@ -0,0 +1,327 @@
+use http::{
+    header::HeaderName, request, HeaderMap, HeaderValue, Request, Response, StatusCode, Version,
+};
+use hyper::Body;
+
+use crate::BodyLimits;
+
+use super::{
+    multipart_boundary, parse_headers, read_body, Error, Multipart, MultipartBuilder,
+    MultipartLimits,
+};
+
+/// `Content-ID` of the part which contains the message, stored in extensions of the message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentId(pub String);
+
+/// `multipart/mixed` response whose parts are `application/http` responses
+///
+/// ```no_run
+/// # use http_util::BatchResponse;
+/// # async fn handle(
+/// #     response: hyper::Response<hyper::Body>,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// let batch = BatchResponse::from_response(response).await?;
+///
+/// for response in batch {
+///     let content_id = BatchResponse::content_id(&response);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct BatchResponse {
+    responses: Vec<Response<Vec<u8>>>,
+}
+
+impl BatchResponse {
+    pub async fn from_response(response: Response<Body>) -> Result<Self, Error> {
+        Self::from_response_with_limits(response, MultipartLimits::default()).await
+    }
+
+    pub async fn from_response_with_limits(
+        mut response: Response<Body>,
+        limits: MultipartLimits,
+    ) -> Result<Self, Error> {
+        let boundary = multipart_boundary(response.headers())?;
+
+        let buf = read_body(response.body_mut(), &limits, &BodyLimits::default()).await?;
+
+        Self::from_bytes_with_limits(buf, &boundary, &limits)
+    }
+
+    pub fn from_bytes(buf: Vec<u8>, boundary: &str) -> Result<Self, Error> {
+        Self::from_bytes_with_limits(buf, boundary, &MultipartLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(
+        buf: Vec<u8>,
+        boundary: &str,
+        limits: &MultipartLimits,
+    ) -> Result<Self, Error> {
+        let responses = Multipart::from_bytes_with_limits(buf, boundary, limits)?
+            .map(|(headers, body)| parse_response(&headers, &body))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { responses })
+    }
+
+    pub fn content_id<B>(response: &Response<B>) -> Option<&str> {
+        response
+            .extensions()
+            .get::<ContentId>()
+            .map(|x| x.0.as_str())
+    }
+
+    pub fn get(&self, content_id: &str) -> Option<&Response<Vec<u8>>> {
+        self.responses
+            .iter()
+            .find(|x| Self::content_id(x) == Some(content_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Response<Vec<u8>>> {
+        self.responses.iter()
+    }
+}
+
+impl IntoIterator for BatchResponse {
+    type Item = Response<Vec<u8>>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.responses.into_iter()
+    }
+}
+
+/// `HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}`
+fn parse_response(part_headers: &HeaderMap, buf: &[u8]) -> Result<Response<Vec<u8>>, Error> {
+    let (head, body) = match twoway::find_bytes(buf, b"\r\n\r\n") {
+        Some(pos) => (&buf[..pos], &buf[(pos + 4)..]),
+        None => (buf, &[][..]),
+    };
+
+    let (status_line, headers) = match twoway::find_bytes(head, b"\r\n") {
+        Some(pos) => (&head[..pos], &head[(pos + 2)..]),
+        None => (head, &[][..]),
+    };
+
+    let status_line = std::str::from_utf8(status_line).map_err(|_| Error::InvalidHttpMessage)?;
+    let mut it = status_line.split_whitespace();
+
+    let version = match it.next() {
+        Some("HTTP/1.0") => Version::HTTP_10,
+        Some("HTTP/1.1") => Version::HTTP_11,
+        Some("HTTP/2" | "HTTP/2.0") => Version::HTTP_2,
+        _ => return Err(Error::InvalidHttpMessage),
+    };
+
+    let status = it
+        .next()
+        .and_then(|x| StatusCode::from_bytes(x.as_bytes()).ok())
+        .ok_or(Error::InvalidHttpMessage)?;
+
+    let mut response = Response::new(body.to_vec());
+
+    *response.version_mut() = version;
+    *response.status_mut() = status;
+    *response.headers_mut() = parse_headers(headers);
+
+    if let Some(content_id) = part_headers.get(CONTENT_ID).and_then(|x| x.to_str().ok()) {
+        response
+            .extensions_mut()
+            .insert(ContentId(content_id.to_owned()));
+    }
+
+    Ok(response)
+}
+
+const CONTENT_ID: HeaderName = HeaderName::from_static("content-id");
+
+/// Encodes requests into a `multipart/mixed` request
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    inner: MultipartBuilder,
+}
+
+impl BatchRequest {
+    pub fn new() -> Self {
+        Self {
+            inner: MultipartBuilder::mixed(),
+        }
+    }
+
+    pub fn request<B: AsRef<[u8]>>(
+        mut self,
+        content_id: &str,
+        request: &Request<B>,
+    ) -> Result<Self, Error> {
+        let mut headers = HeaderMap::new();
+
+        let value = HeaderValue::from_str(content_id)
+            .map_err(|_| Error::InvalidHeaderValue(content_id.to_owned()))?;
+
+        headers.insert(CONTENT_ID, value);
+
+        self.inner = self.inner.http_request(headers, request);
+
+        Ok(self)
+    }
+
+    pub fn content_type(&self) -> String {
+        self.inner.content_type()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_bytes()
+    }
+
+    /// Sets `Content-Type` and body of the request
+    pub fn into_request(self, builder: request::Builder) -> Result<Request<Body>, http::Error> {
+        self.inner.into_request(builder)
+    }
+}
+
+impl Default for BatchRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, Request, Response, StatusCode};
+
+    use super::{BatchRequest, BatchResponse};
+    use crate::multipart::{Error, Limit, Multipart, MultipartLimits};
+
+    #[tokio::test]
+    async fn test_batch_response() {
+        let boundary = "batch_abcd";
+
+        let body = format!(
+            r#"--{boundary}
+Content-Type: application/http
+Content-ID: response-1
+
+HTTP/1.1 200 OK
+Content-Type: application/json; charset=UTF-8
+Vary: Origin
+Vary: X-Origin
+
+{{"name": "projects/35006771263/messages/0"}}
+--{boundary}
+Content-Type: application/http
+Content-ID: response-2
+
+HTTP/1.1 400 BAD REQUEST
+Content-Type: application/json; charset=UTF-8
+
+{{"error": {{"code": 400}}}}
+--{boundary}--"#
+        )
+        .replace('\n', "\r\n");
+
+        let response = Response::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/mixed; boundary={boundary}"),
+            )
+            .body(body.into())
+            .unwrap();
+
+        let batch = BatchResponse::from_response(response).await.unwrap();
+
+        assert_eq!(batch.len(), 2);
+
+        let first = batch.get("response-1").unwrap();
+
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers().get_all(header::VARY).iter().count(), 2);
+        assert_eq!(
+            first.body(),
+            br#"{"name": "projects/35006771263/messages/0"}"#
+        );
+
+        let second = batch.get("response-2").unwrap();
+
+        assert_eq!(second.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(second.body(), br#"{"error": {"code": 400}}"#);
+    }
+
+    #[tokio::test]
+    async fn test_batch_request() {
+        let batch = BatchRequest::new()
+            .request(
+                "<item1>",
+                &Request::post("/v1/messages:send").body("{}").unwrap(),
+            )
+            .unwrap()
+            .request("<item2>", &Request::get("/v1/messages/1").body("").unwrap())
+            .unwrap();
+
+        let mut request = batch.into_request(Request::post("/batch")).unwrap();
+
+        let parts = Multipart::new(&mut request)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0.get("content-id").unwrap(), "<item1>");
+        assert_eq!(parts[0].1, b"POST /v1/messages:send HTTP/1.1\r\n\r\n{}");
+        assert_eq!(parts[1].1, b"GET /v1/messages/1 HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    fn test_batch_request_invalid_content_id() {
+        let r = BatchRequest::new().request("<item\n1>", &Request::get("/").body("").unwrap());
+
+        assert!(matches!(r, Err(Error::InvalidHeaderValue(_))));
+    }
+
+    #[tokio::test]
+    async fn test_batch_response_limits() {
+        let body =
+            "--batch\r\nContent-Type: application/http\r\n\r\nHTTP/1.1 200 OK\r\n\r\n\r\n--batch--";
+
+        let response = || {
+            Response::builder()
+                .header(header::CONTENT_TYPE, "multipart/mixed; boundary=batch")
+                .body(body.into())
+                .unwrap()
+        };
+
+        let r = BatchResponse::from_response_with_limits(
+            response(),
+            MultipartLimits::new().max_total_size(10),
+        )
+        .await;
+
+        assert!(matches!(r, Err(Error::LimitExceeded(Limit::TotalSize(10)))));
+
+        let r = BatchResponse::from_response_with_limits(
+            response(),
+            MultipartLimits::new().max_parts(0),
+        )
+        .await;
+
+        assert!(matches!(r, Err(Error::LimitExceeded(Limit::Parts(0)))));
+
+        let batch = BatchResponse::from_response_with_limits(
+            response(),
+            MultipartLimits::new().max_total_size(body.len()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(batch.len(), 1);
+    }
+}
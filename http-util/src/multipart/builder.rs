@@ -0,0 +1,409 @@
+use http::{
+    header::{self, HeaderName},
+    request, HeaderMap, HeaderValue, Request, Response,
+};
+use hyper::Body;
+use rand::{distributions::Alphanumeric, Rng};
+
+use super::Error;
+use crate::headers::{is_token, quote as quote_param};
+
+/// Builds `multipart/*` body
+///
+/// ```no_run
+/// # use http::Request;
+/// # use http_util::MultipartBuilder;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let multipart = MultipartBuilder::form_data()
+///     .text("name", "value")?
+///     .file("file", "a.txt", "text/plain", b"content".to_vec())?;
+///
+/// let request = multipart.into_request(Request::post("/upload"))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultipartBuilder {
+    subtype: String,
+    boundary: String,
+    parts: Vec<(HeaderMap, Vec<u8>)>,
+}
+
+impl MultipartBuilder {
+    /// `multipart/{subtype}` with random boundary
+    pub fn new(subtype: impl Into<String>) -> Result<Self, Error> {
+        let subtype = subtype.into();
+
+        if !is_token(&subtype) {
+            return Err(Error::InvalidSubtype(subtype));
+        }
+
+        Ok(Self::with_subtype(subtype))
+    }
+
+    fn with_subtype(subtype: String) -> Self {
+        let boundary = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+
+        Self {
+            subtype,
+            boundary,
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn form_data() -> Self {
+        Self::with_subtype("form-data".to_owned())
+    }
+
+    pub fn mixed() -> Self {
+        Self::with_subtype("mixed".to_owned())
+    }
+
+    /// 1 to 70 characters of RFC 2046 5.1.1, not ending with a space
+    pub fn boundary(mut self, boundary: impl Into<String>) -> Result<Self, Error> {
+        let boundary = boundary.into();
+
+        if !is_valid_boundary(&boundary) {
+            return Err(Error::InvalidBoundary(boundary));
+        }
+
+        self.boundary = boundary;
+
+        Ok(self)
+    }
+
+    pub fn get_boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    pub fn part(mut self, headers: HeaderMap, body: impl Into<Vec<u8>>) -> Self {
+        self.parts.push((headers, body.into()));
+
+        self
+    }
+
+    pub fn text(self, name: &str, value: impl Into<String>) -> Result<Self, Error> {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            content_disposition(name, None)?,
+        );
+
+        Ok(self.part(headers, value.into()))
+    }
+
+    pub fn file(
+        self,
+        name: &str,
+        file_name: &str,
+        content_type: &str,
+        body: impl Into<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        self.file_with_headers(name, file_name, content_type, HeaderMap::new(), body)
+    }
+
+    pub fn file_with_headers(
+        self,
+        name: &str,
+        file_name: &str,
+        content_type: &str,
+        mut headers: HeaderMap,
+        body: impl Into<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let content_type = HeaderValue::from_str(content_type)
+            .map_err(|_| Error::InvalidHeaderValue(content_type.to_owned()))?;
+
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            content_disposition(name, Some(file_name))?,
+        );
+        headers.insert(header::CONTENT_TYPE, content_type);
+
+        Ok(self.part(headers, body))
+    }
+
+    /// `Content-Type: application/http` part which contains the request
+    pub fn http_request<B: AsRef<[u8]>>(self, headers: HeaderMap, request: &Request<B>) -> Self {
+        let start_line = format!(
+            "{} {} {:?}",
+            request.method(),
+            request
+                .uri()
+                .path_and_query()
+                .map(|x| x.as_str())
+                .unwrap_or("/"),
+            request.version()
+        );
+
+        self.http_message(headers, start_line, request.headers(), request.body())
+    }
+
+    /// `Content-Type: application/http` part which contains the response
+    pub fn http_response<B: AsRef<[u8]>>(self, headers: HeaderMap, response: &Response<B>) -> Self {
+        let status = response.status();
+        let start_line = format!(
+            "{:?} {} {}",
+            response.version(),
+            status.as_u16(),
+            status.canonical_reason().unwrap_or_default()
+        );
+
+        self.http_message(headers, start_line, response.headers(), response.body())
+    }
+
+    fn http_message(
+        self,
+        mut headers: HeaderMap,
+        start_line: String,
+        message_headers: &HeaderMap,
+        body: impl AsRef<[u8]>,
+    ) -> Self {
+        let mut buf = start_line.into_bytes();
+        buf.extend_from_slice(b"\r\n");
+        write_headers(&mut buf, message_headers);
+        buf.extend_from_slice(b"\r\n");
+        buf.extend_from_slice(body.as_ref());
+
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/http"),
+        );
+
+        self.part(headers, buf)
+    }
+
+    /// `multipart/form-data; boundary=...`, quoted if the boundary is not a token
+    pub fn content_type(&self) -> String {
+        format!(
+            "multipart/{}; boundary={}",
+            self.subtype,
+            quote_param(&self.boundary)
+        )
+    }
+
+    pub fn header(&self) -> (HeaderName, HeaderValue) {
+        // the subtype and the boundary are validated when set
+        let value = HeaderValue::from_str(&self.content_type()).expect("valid content type");
+
+        (header::CONTENT_TYPE, value)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        for (headers, body) in &self.parts {
+            buf.extend_from_slice(b"--");
+            buf.extend_from_slice(self.boundary.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+            write_headers(&mut buf, headers);
+            buf.extend_from_slice(b"\r\n");
+            buf.extend_from_slice(body);
+            buf.extend_from_slice(b"\r\n");
+        }
+
+        buf.extend_from_slice(b"--");
+        buf.extend_from_slice(self.boundary.as_bytes());
+        buf.extend_from_slice(b"--\r\n");
+
+        buf
+    }
+
+    pub fn into_body(self) -> Body {
+        self.to_bytes().into()
+    }
+
+    /// Sets `Content-Type` and body of the request
+    pub fn into_request(self, builder: request::Builder) -> Result<Request<Body>, http::Error> {
+        let (key, value) = self.header();
+
+        builder.header(key, value).body(self.into_body())
+    }
+}
+
+fn write_headers(buf: &mut Vec<u8>, headers: &HeaderMap) {
+    for (key, value) in headers {
+        buf.extend_from_slice(key.as_str().as_bytes());
+        buf.extend_from_slice(b": ");
+        buf.extend_from_slice(value.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+}
+
+fn is_valid_boundary(st: &str) -> bool {
+    (1..=70).contains(&st.len())
+        && !st.ends_with(' ')
+        && st
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&c))
+}
+
+/// `form-data; name="name"; filename="file_name"`
+///
+/// Non-ASCII is sent as raw UTF-8, RFC 7578 4.2 does not allow `filename*`
+fn content_disposition(name: &str, file_name: Option<&str>) -> Result<HeaderValue, Error> {
+    let mut r = format!("form-data; name=\"{}\"", quote(name));
+
+    if let Some(file_name) = file_name {
+        r = format!("{}; filename=\"{}\"", r, quote(file_name));
+    }
+
+    // other control characters are not allowed in header value
+    HeaderValue::from_bytes(r.as_bytes()).map_err(|_| Error::InvalidHeaderValue(r))
+}
+
+/// CR and LF are percent-encoded as browsers do
+fn quote(st: &str) -> String {
+    st.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, HeaderMap, HeaderValue, Request, Response, StatusCode};
+
+    use super::MultipartBuilder;
+    use crate::multipart::{Error, Multipart};
+
+    #[tokio::test]
+    async fn test_multipart_builder() {
+        let multipart = MultipartBuilder::form_data()
+            .text("title", "hello \"world\"")
+            .unwrap()
+            .file("file", "한.txt", "text/plain", b"content\r\n".to_vec())
+            .unwrap();
+
+        assert_eq!(multipart.get_boundary().len(), 32);
+
+        let mut request = multipart.into_request(Request::post("/upload")).unwrap();
+
+        let form = Multipart::new(&mut request)
+            .await
+            .unwrap()
+            .collect_form()
+            .unwrap();
+
+        assert_eq!(form.text("title"), Some("hello \"world\""));
+
+        let file = form.file("file").unwrap();
+
+        assert_eq!(file.file_name(), Some("한.txt"));
+        assert_eq!(file.content_type(), Some("text/plain"));
+        assert_eq!(file.body(), b"content\r\n");
+    }
+
+    #[test]
+    fn test_multipart_builder_http() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-id", HeaderValue::from_static("<item1>"));
+
+        let request = Request::post("/v1/messages?a=b")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(b"{}".to_vec())
+            .unwrap();
+
+        let response = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body("")
+            .unwrap();
+
+        let multipart = MultipartBuilder::mixed()
+            .boundary("batch")
+            .unwrap()
+            .http_request(headers, &request)
+            .http_response(HeaderMap::new(), &response);
+
+        assert_eq!(multipart.content_type(), "multipart/mixed; boundary=batch");
+
+        let expected = "--batch\r\n\
+            content-id: <item1>\r\n\
+            content-type: application/http\r\n\
+            \r\n\
+            POST /v1/messages?a=b HTTP/1.1\r\n\
+            content-type: application/json\r\n\
+            \r\n\
+            {}\r\n\
+            --batch\r\n\
+            content-type: application/http\r\n\
+            \r\n\
+            HTTP/1.1 400 Bad Request\r\n\
+            \r\n\
+            \r\n\
+            --batch--\r\n";
+
+        assert_eq!(String::from_utf8(multipart.to_bytes()).unwrap(), expected);
+
+        let parts = Multipart::from_bytes(multipart.to_bytes(), "batch")
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].1.ends_with(b"\r\n\r\n{}"));
+    }
+
+    #[tokio::test]
+    async fn test_multipart_builder_raw_utf8() {
+        let multipart = MultipartBuilder::form_data()
+            .boundary("gc0p4Jq0M2Yt08j34c0p (boundary)")
+            .unwrap()
+            .file("사진", "a\"b\r\n.png", "image/png", b"png".to_vec())
+            .unwrap();
+
+        assert_eq!(
+            multipart.content_type(),
+            r#"multipart/form-data; boundary="gc0p4Jq0M2Yt08j34c0p (boundary)""#
+        );
+
+        let bytes = String::from_utf8(multipart.to_bytes()).unwrap();
+
+        assert!(bytes.contains(r#"name="사진"; filename="a\"b%0D%0A.png""#));
+        assert!(!bytes.contains("filename*"));
+
+        let mut request = multipart.into_request(Request::post("/upload")).unwrap();
+
+        let form = Multipart::new(&mut request)
+            .await
+            .unwrap()
+            .collect_form()
+            .unwrap();
+
+        assert_eq!(
+            form.file("사진").unwrap().file_name(),
+            Some("a\"b%0D%0A.png")
+        );
+    }
+
+    #[test]
+    fn test_multipart_builder_invalid() {
+        assert!(matches!(
+            MultipartBuilder::mixed().boundary("trailing "),
+            Err(Error::InvalidBoundary(_))
+        ));
+        assert!(matches!(
+            MultipartBuilder::mixed().boundary("a".repeat(71)),
+            Err(Error::InvalidBoundary(_))
+        ));
+        assert!(matches!(
+            MultipartBuilder::mixed().boundary("semi;colon"),
+            Err(Error::InvalidBoundary(_))
+        ));
+        assert!(matches!(
+            MultipartBuilder::new("form data"),
+            Err(Error::InvalidSubtype(_))
+        ));
+        assert!(matches!(
+            MultipartBuilder::form_data().text("a\0", "value"),
+            Err(Error::InvalidHeaderValue(_))
+        ));
+        assert!(matches!(
+            MultipartBuilder::form_data().file("file", "a.txt", "text/plain\n", Vec::new()),
+            Err(Error::InvalidHeaderValue(_))
+        ));
+    }
+}
@@ -0,0 +1,278 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use http::{header, HeaderMap};
+use percent_encoding::percent_decode_str;
+
+use super::Error;
+
+/// `Content-Disposition: form-data; name="file"; filename="a.txt"`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ContentDisposition {
+    pub name: Option<String>,
+    pub file_name: Option<String>,
+}
+
+impl ContentDisposition {
+    pub fn parse(st: &str) -> Self {
+        let mut name = None;
+        let mut file_name = None;
+        let mut ext_file_name = None;
+
+        for (key, value) in params(st) {
+            match key.to_ascii_lowercase().as_str() {
+                "name" => name = Some(value.into_owned()),
+                "filename" => file_name = Some(value.into_owned()),
+                "filename*" => ext_file_name = decode_ext_value(&value),
+                _ => {}
+            }
+        }
+
+        Self {
+            name,
+            // filename* takes precedence over filename
+            file_name: ext_file_name.or(file_name),
+        }
+    }
+
+    /// Browsers send raw UTF-8 in `filename`, which `HeaderValue::to_str` rejects
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(header::CONTENT_DISPOSITION)
+            .map(|x| Self::parse(&String::from_utf8_lossy(x.as_bytes())))
+            .unwrap_or_default()
+    }
+}
+
+/// Parses `; key=value; key="quoted \"value\""` after the first `;`
+pub(crate) fn params(st: &str) -> Vec<(&str, Cow<'_, str>)> {
+    let mut r = Vec::new();
+    let mut rest = match st.split_once(';') {
+        Some((_, rest)) => rest,
+        None => return r,
+    };
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ';' || c.is_ascii_whitespace());
+
+        let (key, after_key) = match rest.split_once('=') {
+            Some(x) => x,
+            None => break,
+        };
+
+        // ignore parameters without value
+        let key = key.rsplit(';').next().unwrap_or(key).trim();
+        let after_key = after_key.trim_start();
+
+        if let Some(quoted) = after_key.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, c)) = chars.next() {
+                            value.push(c);
+                        }
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+
+            r.push((key, Cow::Owned(value)));
+            rest = &quoted[end..];
+        } else {
+            let (value, after_value) = after_key.split_once(';').unwrap_or((after_key, ""));
+
+            r.push((key, Cow::Borrowed(value.trim())));
+            rest = after_value;
+        }
+    }
+
+    r
+}
+
+/// RFC 5987 `UTF-8''%e2%82%ac%20rates.txt`
+fn decode_ext_value(st: &str) -> Option<String> {
+    let mut it = st.splitn(3, '\'');
+
+    let charset = it.next()?;
+    let _language = it.next()?;
+    let value = percent_decode_str(it.next()?);
+
+    if charset.eq_ignore_ascii_case("utf-8") {
+        value.decode_utf8().ok().map(Cow::into_owned)
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(value.map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    headers: HeaderMap,
+    content_disposition: ContentDisposition,
+    body: Vec<u8>,
+}
+
+impl Field {
+    pub fn new(headers: HeaderMap, body: Vec<u8>) -> Self {
+        let content_disposition = ContentDisposition::from_headers(&headers);
+
+        Self {
+            headers,
+            content_disposition,
+            body,
+        }
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.content_disposition.name.as_deref()
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.content_disposition.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_name().is_some()
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    pub fn text(&self) -> Result<&str, Error> {
+        std::str::from_utf8(&self.body)
+            .map_err(|_| Error::NotUtf8Field(self.name().unwrap_or_default().to_owned()))
+    }
+}
+
+impl From<(HeaderMap, Vec<u8>)> for Field {
+    fn from((headers, body): (HeaderMap, Vec<u8>)) -> Self {
+        Self::new(headers, body)
+    }
+}
+
+/// Text fields grouped by name, and file fields in order
+#[derive(Debug, Default, Clone)]
+pub struct Form {
+    pub texts: HashMap<String, Vec<String>>,
+    pub files: Vec<Field>,
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The first value of the text field
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.texts
+            .get(name)
+            .and_then(|xs| xs.first())
+            .map(|x| x.as_str())
+    }
+
+    pub fn text_all(&self, name: &str) -> &[String] {
+        self.texts.get(name).map(|xs| xs.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn file(&self, name: &str) -> Option<&Field> {
+        self.files.iter().find(|x| x.name() == Some(name))
+    }
+
+    pub fn push(&mut self, field: Field) -> Result<(), Error> {
+        if field.is_file() {
+            self.files.push(field);
+        } else {
+            let text = field.text()?.to_owned();
+            let name = field.name().unwrap_or_default().to_owned();
+
+            self.texts.entry(name).or_default().push(text);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, HeaderMap, HeaderValue};
+
+    use super::{ContentDisposition, Field};
+
+    #[test]
+    fn test_content_disposition() {
+        let x = ContentDisposition::parse(r#"form-data; name="field"; filename="a \"b\";c.txt""#);
+
+        assert_eq!(x.name.as_deref(), Some("field"));
+        assert_eq!(x.file_name.as_deref(), Some(r#"a "b";c.txt"#));
+
+        let x = ContentDisposition::parse(
+            "attachment; filename=rates.txt; filename*=UTF-8''%e2%82%ac%20rates.txt",
+        );
+
+        assert_eq!(x.name, None);
+        assert_eq!(x.file_name.as_deref(), Some("€ rates.txt"));
+
+        let x = ContentDisposition::parse("form-data; name=plain");
+
+        assert_eq!(x.name.as_deref(), Some("plain"));
+        assert_eq!(x.file_name, None);
+    }
+
+    #[test]
+    fn test_field() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_static(r#"form-data; name="avatar"; filename="me.png""#),
+        );
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("image/png"));
+
+        let field = Field::from((headers, vec![0x89, 0x50]));
+
+        assert_eq!(field.name(), Some("avatar"));
+        assert_eq!(field.file_name(), Some("me.png"));
+        assert_eq!(field.content_type(), Some("image/png"));
+        assert!(field.is_file());
+        assert_eq!(field.body(), &[0x89, 0x50]);
+    }
+
+    #[test]
+    fn test_field_utf8_file_name() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_bytes(r#"form-data; name="file"; filename="한.txt""#.as_bytes())
+                .unwrap(),
+        );
+
+        let field = Field::from((headers, Vec::new()));
+
+        assert_eq!(field.name(), Some("file"));
+        assert_eq!(field.file_name(), Some("한.txt"));
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+/// Which limit of [`MultipartLimits`] is exceeded
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Limit {
+    #[error("more than {0} parts")]
+    Parts(usize),
+    #[error("part {name:?} is larger than {limit} bytes")]
+    PartSize { name: Option<String>, limit: usize },
+    #[error("header block is larger than {0} bytes")]
+    HeaderSize(usize),
+    #[error("body is larger than {0} bytes")]
+    TotalSize(usize),
+}
+
+/// Unlimited by default
+#[derive(Debug, Default, Clone)]
+pub struct MultipartLimits {
+    pub max_parts: Option<usize>,
+    /// Bytes
+    pub max_part_size: Option<usize>,
+    /// Bytes
+    pub max_header_size: Option<usize>,
+    /// Bytes
+    pub max_total_size: Option<usize>,
+    /// Overrides `max_part_size` by field name
+    pub max_field_sizes: HashMap<String, usize>,
+}
+
+impl MultipartLimits {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn max_parts(mut self, max_parts: usize) -> Self {
+        self.max_parts.replace(max_parts);
+
+        self
+    }
+
+    pub fn max_part_size(mut self, max_part_size: usize) -> Self {
+        self.max_part_size.replace(max_part_size);
+
+        self
+    }
+
+    pub fn max_header_size(mut self, max_header_size: usize) -> Self {
+        self.max_header_size.replace(max_header_size);
+
+        self
+    }
+
+    pub fn max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size.replace(max_total_size);
+
+        self
+    }
+
+    pub fn max_field_size(mut self, name: impl Into<String>, max_size: usize) -> Self {
+        self.max_field_sizes.insert(name.into(), max_size);
+
+        self
+    }
+
+    pub fn part_size_of(&self, name: Option<&str>) -> Option<usize> {
+        name.and_then(|name| self.max_field_sizes.get(name))
+            .copied()
+            .or(self.max_part_size)
+    }
+
+    pub(super) fn check_parts(&self, count: usize) -> Result<(), Limit> {
+        match self.max_parts {
+            Some(limit) if count > limit => Err(Limit::Parts(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn check_part_size(&self, name: Option<&str>, size: usize) -> Result<(), Limit> {
+        match self.part_size_of(name) {
+            Some(limit) if size > limit => Err(Limit::PartSize {
+                name: name.map(str::to_owned),
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn check_header_size(&self, size: usize) -> Result<(), Limit> {
+        match self.max_header_size {
+            Some(limit) if size > limit => Err(Limit::HeaderSize(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn check_total_size(&self, size: usize) -> Result<(), Limit> {
+        match self.max_total_size {
+            Some(limit) if size > limit => Err(Limit::TotalSize(limit)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, Request, StatusCode};
+    use hyper::Body;
+
+    use super::{Limit, MultipartLimits};
+    use crate::{
+        multipart::{Error, Multipart, MultipartStream},
+        BodyLimits,
+    };
+
+    const BODY: &str = "--xyz\r\n\
+        Content-Disposition: form-data; name=\"text\"\r\n\
+        \r\n\
+        hello\r\n\
+        --xyz\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        \r\n\
+        0123456789\r\n\
+        --xyz--";
+
+    fn request() -> Request<Body> {
+        Request::builder()
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=xyz")
+            .body(BODY.into())
+            .unwrap()
+    }
+
+    async fn parse(limits: MultipartLimits) -> (Result<(), Error>, Result<(), Error>) {
+        let buffered = Multipart::new_with_limits(&mut request(), limits.clone())
+            .await
+            .map(|_| ());
+
+        let streamed = async {
+            let mut multipart = MultipartStream::new_with_limits(&mut request(), limits)?;
+
+            while let Some(part) = multipart.next_part().await? {
+                part.bytes().await?;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        (buffered, streamed)
+    }
+
+    #[tokio::test]
+    async fn test_multipart_limits() {
+        let (buffered, streamed) = parse(MultipartLimits::new()).await;
+
+        assert!(buffered.is_ok());
+        assert!(streamed.is_ok());
+
+        let cases = [
+            (MultipartLimits::new().max_parts(1), Limit::Parts(1)),
+            (
+                MultipartLimits::new().max_part_size(5),
+                Limit::PartSize {
+                    name: Some("file".to_owned()),
+                    limit: 5,
+                },
+            ),
+            (
+                MultipartLimits::new()
+                    .max_part_size(10)
+                    .max_field_size("text", 4),
+                Limit::PartSize {
+                    name: Some("text".to_owned()),
+                    limit: 4,
+                },
+            ),
+            (
+                MultipartLimits::new().max_header_size(40),
+                Limit::HeaderSize(40),
+            ),
+            (
+                MultipartLimits::new().max_total_size(100),
+                Limit::TotalSize(100),
+            ),
+        ];
+
+        for (limits, expected) in cases {
+            let (buffered, streamed) = parse(limits).await;
+
+            match (buffered, streamed) {
+                (Err(Error::LimitExceeded(a)), Err(Error::LimitExceeded(b))) => {
+                    assert_eq!(a, expected);
+                    assert_eq!(b, expected);
+                }
+                r => panic!("{r:?}"),
+            }
+        }
+
+        let mut buffered_request = request();
+        buffered_request
+            .extensions_mut()
+            .insert(BodyLimits::new().max_bytes(100));
+
+        let err = Multipart::new(&mut buffered_request).await.err().unwrap();
+
+        assert!(matches!(err, Error::LimitExceeded(Limit::TotalSize(100))));
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let mut streamed_request = request();
+        streamed_request
+            .extensions_mut()
+            .insert(BodyLimits::new().max_bytes(100));
+
+        let err = MultipartStream::new_with_limits(&mut streamed_request, MultipartLimits::new())
+            .unwrap()
+            .collect_form()
+            .await
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::LimitExceeded(Limit::TotalSize(100))));
+
+        let limits = MultipartLimits::new()
+            .max_part_size(5)
+            .max_field_size("file", 10);
+
+        let (buffered, streamed) = parse(limits).await;
+
+        assert!(buffered.is_ok());
+        assert!(streamed.is_ok());
+    }
+}
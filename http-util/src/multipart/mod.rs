@@ -0,0 +1,566 @@
+use std::ops::Range;
+
+use http::{
+    header::{self, HeaderName},
+    HeaderMap, HeaderValue, Request, StatusCode,
+};
+use hyper::Body;
+
+use crate::{headers::MediaType, read_chunks, BodyLimits, ReadChunks};
+
+mod batch;
+mod builder;
+mod field;
+mod limits;
+mod stream;
+
+pub use batch::*;
+pub use builder::*;
+pub use field::*;
+pub use limits::*;
+pub use stream::*;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Boundary")]
+    Boundary,
+    #[error("Quoted boundary is not closed")]
+    QuotedBoundary,
+    #[error("Missing first boundary")]
+    MissingFirstBoundary,
+    #[error("Missing closing boundary")]
+    MissingClosingBoundary,
+    #[error("Missing header terminator")]
+    MissingHeaderTerminator,
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(#[from] Limit),
+    #[error("Body: {0}")]
+    Body(#[from] hyper::Error),
+    #[error("Timeout")]
+    Timeout,
+    #[error("Not multipart")]
+    NotMultipart,
+    #[error("Not utf-8 field: {0}")]
+    NotUtf8Field(String),
+    #[error("Invalid http message")]
+    InvalidHttpMessage,
+    #[error("Invalid boundary: {0}")]
+    InvalidBoundary(String),
+    #[error("Invalid subtype: {0}")]
+    InvalidSubtype(String),
+    #[error("Invalid header value: {0}")]
+    InvalidHeaderValue(String),
+}
+
+impl Error {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::LimitExceeded(Limit::TotalSize(_)) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Timeout => StatusCode::REQUEST_TIMEOUT,
+            Self::NotMultipart => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+pub struct Multipart {
+    buf: Vec<u8>,
+    /// Headers and body range of each part
+    parts: std::vec::IntoIter<(HeaderMap, Range<usize>)>,
+}
+
+impl Multipart {
+    pub async fn new(request: &mut Request<Body>) -> Result<Multipart, Error> {
+        Self::new_with_limits(request, MultipartLimits::default()).await
+    }
+
+    pub async fn new_with_limits(
+        request: &mut Request<Body>,
+        limits: MultipartLimits,
+    ) -> Result<Multipart, Error> {
+        let boundary = multipart_boundary(request.headers())?;
+
+        let body_limits = request
+            .extensions()
+            .get::<BodyLimits>()
+            .cloned()
+            .unwrap_or_default();
+
+        let buf = read_body(request.body_mut(), &limits, &body_limits).await?;
+
+        Self::from_bytes_with_limits(buf, &boundary, &limits)
+    }
+
+    pub fn from_bytes(buf: Vec<u8>, boundary: &str) -> Result<Multipart, Error> {
+        Self::from_bytes_with_limits(buf, boundary, &MultipartLimits::default())
+    }
+
+    pub fn from_bytes_with_limits(
+        buf: Vec<u8>,
+        boundary: &str,
+        limits: &MultipartLimits,
+    ) -> Result<Multipart, Error> {
+        limits.check_total_size(buf.len())?;
+
+        let parts = split_parts(&buf, boundary.as_bytes(), limits)?.into_iter();
+
+        Ok(Self { buf, parts })
+    }
+
+    pub fn fields(self) -> impl Iterator<Item = Field> {
+        self.map(Field::from)
+    }
+
+    pub fn collect_form(self) -> Result<Form, Error> {
+        let mut form = Form::new();
+
+        for field in self.fields() {
+            form.push(field)?;
+        }
+
+        Ok(form)
+    }
+}
+
+/// `multipart/form-data; boundary=abcd` or `multipart/form-data; boundary="ab:cd"`
+fn multipart_boundary(headers: &HeaderMap) -> Result<String, Error> {
+    let media_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.parse::<MediaType>().ok())
+        .filter(|x| x.type_() == "multipart")
+        .ok_or(Error::NotMultipart)?;
+
+    let boundary = media_type.get_param("boundary").ok_or(Error::Boundary)?;
+
+    // the closing quote is missing
+    if boundary.starts_with('"') {
+        return Err(Error::QuotedBoundary);
+    }
+
+    if boundary.is_empty() {
+        return Err(Error::Boundary);
+    }
+
+    Ok(boundary.to_owned())
+}
+
+async fn read_body(
+    body: &mut Body,
+    limits: &MultipartLimits,
+    body_limits: &BodyLimits,
+) -> Result<Vec<u8>, Error> {
+    let max_bytes = match (limits.max_total_size, body_limits.max_bytes) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+
+    let body_limits = BodyLimits {
+        max_bytes,
+        timeout: body_limits.timeout,
+    };
+
+    body.read_chunks_with(&body_limits)
+        .await
+        .map_err(|err| match err {
+            read_chunks::Error::PayloadTooLarge(limit) => Limit::TotalSize(limit).into(),
+            read_chunks::Error::Timeout => Error::Timeout,
+            read_chunks::Error::Body(err) => Error::Body(err),
+        })
+}
+
+fn split_parts(
+    buf: &[u8],
+    boundary: &[u8],
+    limits: &MultipartLimits,
+) -> Result<Vec<(HeaderMap, Range<usize>)>, Error> {
+    let delimiter = [b"\r\n--", boundary].concat();
+
+    let mut parts = Vec::new();
+
+    // the first boundary may be at the very beginning of the body
+    let mut pos = if buf.starts_with(&delimiter[2..]) {
+        delimiter.len() - 2
+    } else {
+        twoway::find_bytes(buf, &delimiter).ok_or(Error::MissingFirstBoundary)? + delimiter.len()
+    };
+
+    loop {
+        let rest = &buf[pos..];
+
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+
+        // transport padding
+        pos += twoway::find_bytes(rest, b"\r\n").ok_or(Error::MissingClosingBoundary)? + 2;
+
+        let rest = &buf[pos..];
+
+        let next = twoway::find_bytes(rest, &delimiter).ok_or(Error::MissingClosingBoundary)?;
+
+        let (headers, body_start) = if next == 0 {
+            // an empty part, the CRLF belongs to the delimiter
+            (HeaderMap::new(), 0)
+        } else if rest.starts_with(b"\r\n") {
+            // a part without headers
+            (HeaderMap::new(), 2)
+        } else {
+            let crlf_pos = twoway::find_bytes(&rest[..next], b"\r\n\r\n");
+
+            limits.check_header_size(crlf_pos.unwrap_or(next))?;
+
+            match crlf_pos {
+                Some(crlf_pos) => (parse_headers(&rest[..crlf_pos]), crlf_pos + 4),
+                None => return Err(Error::MissingHeaderTerminator),
+            }
+        };
+
+        limits.check_parts(parts.len() + 1)?;
+        limits.check_part_size(
+            ContentDisposition::from_headers(&headers).name.as_deref(),
+            next - body_start,
+        )?;
+
+        parts.push((headers, (pos + body_start)..(pos + next)));
+
+        pos += next + delimiter.len();
+    }
+}
+
+fn parse_headers(buf: &[u8]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    // each value on its own, so a raw UTF-8 value does not drop the others
+    for line in buf.split(|c| *c == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        let header = line.iter().position(|c| *c == b':').and_then(|i| {
+            Some((
+                HeaderName::from_bytes(line[..i].trim_ascii()).ok()?,
+                HeaderValue::from_bytes(line[i + 1..].trim_ascii()).ok()?,
+            ))
+        });
+
+        if let Some((name, value)) = header {
+            headers.append(name, value);
+        }
+    }
+
+    headers
+}
+
+impl Iterator for Multipart {
+    type Item = (HeaderMap, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (headers, body) = self.parts.next()?;
+
+        Some((headers, self.buf[body].to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::discriminant;
+
+    use http::{header, Request};
+    use hyper::Body;
+
+    use super::{Error, Multipart, MultipartStream};
+
+    #[tokio::test]
+    async fn test_multipart() {
+        let boundary = "abhjdahkdhfsikldhjfliawefrkhkahskda";
+
+        let body = format!(
+            r#"--{boundary}
+Content-Type: application/http
+Content-ID: response-
+
+HTTP/1.1 200 OK
+Content-Type: application/json; charset=UTF-8
+Vary: Origin
+Vary: X-Origin
+Vary: Referer
+
+{{
+"name": "projects/35006771263/messages/0:1570471792141125%43c11b7043c11b70"
+}}
+
+--{boundary}
+Content-Type: application/http
+Content-ID: response-
+
+HTTP/1.1 400 BAD REQUEST
+Content-Type: application/json; charset=UTF-8
+Vary: Origin
+Vary: X-Origin
+Vary: Referer
+
+{{
+"error": {{
+    "code": 400,
+    "message": "The registration token is not a valid FCM registration token",
+    "status": "INVALID_ARGUMENT"
+  }}
+}}
+
+--{boundary}
+Content-Type: application/http
+Content-ID: response-
+
+HTTP/1.1 200 OK
+Content-Type: application/json; charset=UTF-8
+Vary: Origin
+Vary: X-Origin
+Vary: Referer
+
+{{adnsdkjasdh
+"name": "projects/35006771263/messages/0:1570471792141696%43c11b7043c11b70"
+}}
+
+--{boundary}--"#
+        )
+        .replace('\n', "\r\n")
+        .replace("adnsdkjasdh", "\r\r\r\r\r\r\r\r");
+
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body.into())
+            .unwrap();
+
+        let multipart = Multipart::new(&mut request).await.unwrap();
+
+        // let a = String::from_utf8(multipart.next().unwrap()).unwrap();
+
+        // println!("{a}");
+
+        for (headers, a) in multipart {
+            let content_type = headers.get("Content-Type").unwrap().to_str().unwrap();
+            let content_id = headers.get("Content-ID").unwrap().to_str().unwrap();
+
+            println!("Content-Type: {content_type}");
+            println!("Content-ID: {content_id}");
+            println!();
+
+            let a = String::from_utf8(a).unwrap();
+
+            let r = a.contains('\r');
+
+            assert!(r);
+
+            println!("{a}");
+
+            // println!("{}", a.replace('\r', "\\r"));
+
+            println!("------------------");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_collect_form() {
+        let boundary = "abhjdahkdhfsikldhjfliawefrkhkahskda";
+
+        let body = format!(
+            r#"--{boundary}
+Content-Disposition: form-data; name="tag"
+
+a
+--{boundary}
+Content-Disposition: form-data; name="tag"
+
+b
+--{boundary}
+Content-Disposition: form-data; name="file"; filename*=UTF-8''%ED%95%9C.txt
+Content-Type: text/plain
+
+content
+--{boundary}
+Content-Disposition: form-data; name="photo"; filename="사진.png"
+Content-Type: image/png
+
+png
+--{boundary}--"#
+        )
+        .replace('\n', "\r\n");
+
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body.into())
+            .unwrap();
+
+        let form = Multipart::new(&mut request)
+            .await
+            .unwrap()
+            .collect_form()
+            .unwrap();
+
+        assert_eq!(form.text_all("tag"), ["a", "b"]);
+
+        let file = form.file("file").unwrap();
+
+        assert_eq!(file.file_name(), Some("한.txt"));
+        assert_eq!(file.content_type(), Some("text/plain"));
+        assert_eq!(file.body(), b"content");
+
+        // raw UTF-8 as browsers send it
+        let photo = form.file("photo").unwrap();
+
+        assert_eq!(photo.file_name(), Some("사진.png"));
+        assert_eq!(photo.content_type(), Some("image/png"));
+    }
+
+    async fn parse(
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> (Result<usize, Error>, Result<usize, Error>) {
+        let request = || {
+            Request::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .body(Body::from(body.clone()))
+                .unwrap()
+        };
+
+        let buffered = Multipart::new(&mut request()).await.map(|x| x.count());
+
+        let streamed = async {
+            let mut multipart = MultipartStream::new(&mut request())?;
+            let mut count = 0;
+
+            while let Some(part) = multipart.next_part().await? {
+                part.bytes().await?;
+                count += 1;
+            }
+
+            Ok(count)
+        }
+        .await;
+
+        (buffered, streamed)
+    }
+
+    #[tokio::test]
+    async fn test_multipart_malformed() {
+        let content_type = "multipart/form-data; boundary=xyz";
+
+        let corpus = [
+            ("", Error::MissingFirstBoundary),
+            ("no boundary at all", Error::MissingFirstBoundary),
+            ("--xy", Error::MissingFirstBoundary),
+            ("--xyz", Error::MissingClosingBoundary),
+            ("--xyz\r\nA: b\r\n\r\nbody", Error::MissingClosingBoundary),
+            (
+                "--xyz\r\nA: b\r\nbody\r\n--xyz--",
+                Error::MissingHeaderTerminator,
+            ),
+            (
+                "--xyz\r\n\r\na\r\n--xyz\r\nB: c\r\n\r\n",
+                Error::MissingClosingBoundary,
+            ),
+            ("--xyz\r\n--xyz--", Error::MissingClosingBoundary),
+            ("--xyz\r\nA: b", Error::MissingClosingBoundary),
+        ];
+
+        for (body, expected) in corpus {
+            let expected = discriminant(&expected);
+            let (buffered, streamed) = parse(content_type, body.into()).await;
+
+            assert_eq!(discriminant(&buffered.unwrap_err()), expected, "{body:?}");
+            assert_eq!(discriminant(&streamed.unwrap_err()), expected, "{body:?}");
+        }
+
+        for content_type in [
+            "multipart/form-data",
+            "multipart/form-data; boundary=",
+            "multipart/form-data; boundary=\"xyz",
+        ] {
+            let (buffered, streamed) = parse(content_type, b"--xyz--".to_vec()).await;
+
+            assert!(matches!(
+                buffered.unwrap_err(),
+                Error::Boundary | Error::QuotedBoundary
+            ));
+            assert!(matches!(
+                streamed.unwrap_err(),
+                Error::Boundary | Error::QuotedBoundary
+            ));
+        }
+
+        for content_type in [
+            "",
+            "text/plain; boundary=xyz",
+            "text/multipart/form-data; boundary=xyz",
+            "application/x-multipart/; boundary=xyz",
+        ] {
+            let (buffered, streamed) = parse(content_type, b"--xyz--".to_vec()).await;
+
+            assert!(matches!(buffered.unwrap_err(), Error::NotMultipart));
+            assert!(matches!(streamed.unwrap_err(), Error::NotMultipart));
+        }
+
+        let (buffered, streamed) = parse(
+            "Multipart/Form-Data; Boundary=xyz",
+            b"--xyz\r\n\r\nbody\r\n--xyz--".to_vec(),
+        )
+        .await;
+
+        assert_eq!(buffered.unwrap(), 1);
+        assert_eq!(streamed.unwrap(), 1);
+
+        let (buffered, streamed) = parse(
+            "multipart/form-data; boundary=\"x:y z\"; charset=utf-8",
+            b"--x:y z\r\n\r\nbody\r\n--x:y z--".to_vec(),
+        )
+        .await;
+
+        assert_eq!(buffered.unwrap(), 1);
+        assert_eq!(streamed.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_multipart_truncated_and_mutated() {
+        let content_type = "multipart/form-data; boundary=xyz";
+        let body = b"--xyz\r\nA: b\r\n\r\none\r\n--xyz\r\n\r\ntwo\r\n--xyz--".to_vec();
+
+        // every truncated body is an error, never a panic
+        for i in 0..body.len() - 1 {
+            let (buffered, streamed) = parse(content_type, body[..i].to_vec()).await;
+
+            assert!(buffered.is_err(), "{i}");
+            assert!(streamed.is_err(), "{i}");
+        }
+
+        // an empty part without headers
+        for body in ["--xyz\r\n\r\n--xyz--", "--xyz\r\n\r\n--xyz\r\n\r\n--xyz--"] {
+            let count = body.matches("\r\n\r\n").count();
+            let (buffered, streamed) = parse(content_type, body.into()).await;
+
+            assert_eq!(buffered.unwrap(), count, "{body:?}");
+            assert_eq!(streamed.unwrap(), count, "{body:?}");
+        }
+
+        // bodies with a range removed must not panic
+        for i in 0..body.len() {
+            for j in i + 1..=body.len() {
+                let _ = parse(content_type, [&body[..i], &body[j..]].concat()).await;
+            }
+        }
+
+        // mutated bodies must not panic
+        for i in 0..body.len() {
+            for b in [b'\r', b'\n', b'-', b':', b'"', 0xff] {
+                let mut body = body.clone();
+                body[i] = b;
+
+                let _ = parse(content_type, body).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,532 @@
+use std::{
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use futures::Stream;
+use http::{header, HeaderMap, Request};
+use hyper::{
+    body::{Bytes, HttpBody},
+    Body,
+};
+use tokio::time::Sleep;
+
+use super::{
+    multipart_boundary, parse_headers, ContentDisposition, Error, Field, Form, MultipartLimits,
+};
+use crate::BodyLimits;
+
+enum State {
+    /// Before the first boundary
+    Preamble,
+    /// Right after a boundary, `--` or transport padding follows
+    Boundary,
+    Headers,
+    Body,
+    End,
+}
+
+/// Reads `multipart/*` body chunk by chunk instead of buffering the whole body.
+///
+/// ```no_run
+/// # use std::io::Write;
+/// # use http_util::MultipartStream;
+/// # async fn handle(
+/// #     mut request: hyper::Request<hyper::Body>,
+/// #     mut file: std::fs::File,
+/// # ) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut multipart = MultipartStream::new(&mut request)?;
+///
+/// while let Some(mut part) = multipart.next_part().await? {
+///     while let Some(chunk) = part.chunk().await {
+///         file.write_all(&chunk?)?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultipartStream {
+    body: Body,
+    /// `\r\n--boundary`
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    state: State,
+    limits: MultipartLimits,
+    /// Bytes read from the body
+    total_size: usize,
+    parts: usize,
+    /// Field name of the current part
+    part_name: Option<String>,
+    part_size: usize,
+    timeout: Option<Duration>,
+    /// Started on the first read of the body
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl MultipartStream {
+    pub fn new(request: &mut Request<Body>) -> Result<Self, Error> {
+        Self::new_with_limits(request, MultipartLimits::default())
+    }
+
+    pub fn new_with_limits(
+        request: &mut Request<Body>,
+        limits: MultipartLimits,
+    ) -> Result<Self, Error> {
+        let boundary = multipart_boundary(request.headers())?;
+
+        let body_limits = request
+            .extensions()
+            .get::<BodyLimits>()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(
+            Self::with_boundary(std::mem::take(request.body_mut()), boundary)
+                .limits(limits)
+                .body_limits(&body_limits),
+        )
+    }
+
+    pub fn with_boundary(body: Body, boundary: impl AsRef<str>) -> Self {
+        let delimiter = [b"\r\n--", boundary.as_ref().as_bytes()].concat();
+
+        Self {
+            body,
+            delimiter,
+            // the first boundary may be at the very beginning of the body,
+            // so it is not preceded by CRLF
+            buf: b"\r\n".to_vec(),
+            state: State::Preamble,
+            limits: MultipartLimits::default(),
+            total_size: 0,
+            parts: 0,
+            part_name: None,
+            part_size: 0,
+            timeout: None,
+            deadline: None,
+        }
+    }
+
+    pub fn limits(mut self, limits: MultipartLimits) -> Self {
+        self.limits = limits;
+
+        self
+    }
+
+    /// `max_bytes` is merged into `max_total_size` of the limits set so far, `timeout` covers
+    /// reading the whole body
+    pub fn body_limits(mut self, body_limits: &BodyLimits) -> Self {
+        self.limits.max_total_size = match (self.limits.max_total_size, body_limits.max_bytes) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.timeout = body_limits.timeout;
+
+        self
+    }
+
+    /// Skips the rest of the current part, if any
+    pub async fn next_part(&mut self) -> Result<Option<Part<'_>>, Error> {
+        let headers = poll_fn(|cx| self.poll_next_headers(cx)).await?;
+
+        Ok(headers.map(|headers| Part {
+            content_disposition: ContentDisposition::from_headers(&headers),
+            headers,
+            stream: self,
+        }))
+    }
+
+    /// Reads every part into memory
+    pub async fn collect_form(mut self) -> Result<Form, Error> {
+        let mut form = Form::new();
+
+        while let Some(part) = self.next_part().await? {
+            form.push(part.field().await?)?;
+        }
+
+        Ok(form)
+    }
+
+    /// Returns `false` if the body has reached the end
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<bool, Error>> {
+        if let Some(timeout) = self.timeout {
+            let deadline = self
+                .deadline
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+
+            if deadline.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(Error::Timeout));
+            }
+        }
+
+        match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+            Some(chunk) => {
+                let chunk = chunk?;
+
+                self.total_size += chunk.len();
+                self.limits.check_total_size(self.total_size)?;
+
+                self.buf.extend_from_slice(&chunk);
+                Poll::Ready(Ok(true))
+            }
+            None => Poll::Ready(Ok(false)),
+        }
+    }
+
+    fn poll_next_headers(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Error>> {
+        loop {
+            match self.state {
+                State::Preamble | State::Body => {
+                    if let Some(pos) = twoway::find_bytes(&self.buf, &self.delimiter) {
+                        self.buf.drain(..pos + self.delimiter.len());
+                        self.state = State::Boundary;
+                        continue;
+                    }
+
+                    // keep the tail which can be the beginning of the delimiter
+                    let keep = self.buf.len().min(self.delimiter.len() - 1);
+                    self.buf.drain(..self.buf.len() - keep);
+                }
+                State::Boundary => {
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::End;
+                        continue;
+                    }
+
+                    if let Some(pos) = twoway::find_bytes(&self.buf, b"\r\n") {
+                        self.buf.drain(..pos + 2);
+                        self.state = State::Headers;
+                        continue;
+                    }
+                }
+                State::Headers => {
+                    let headers = if self.buf.starts_with(&self.delimiter) {
+                        // an empty part, the delimiter is read as the body ends
+                        Some(HeaderMap::new())
+                    } else if self.delimiter.starts_with(&self.buf) {
+                        // can not tell an empty part from a part without headers yet
+                        None
+                    } else if self.buf.starts_with(b"\r\n") {
+                        // a part without headers
+                        self.buf.drain(..2);
+                        Some(HeaderMap::new())
+                    } else if let Some(pos) = twoway::find_bytes(&self.buf, b"\r\n\r\n") {
+                        self.limits.check_header_size(pos)?;
+
+                        let headers = parse_headers(&self.buf[..pos]);
+                        self.buf.drain(..pos + 4);
+                        Some(headers)
+                    } else {
+                        // the terminator can be split into the next chunk
+                        self.limits
+                            .check_header_size(self.buf.len().saturating_sub(3))?;
+                        None
+                    };
+
+                    if let Some(headers) = headers {
+                        self.parts += 1;
+                        self.limits.check_parts(self.parts)?;
+
+                        self.part_name = ContentDisposition::from_headers(&headers).name;
+                        self.part_size = 0;
+                        self.state = State::Body;
+
+                        return Poll::Ready(Ok(Some(headers)));
+                    }
+                }
+                State::End => return Poll::Ready(Ok(None)),
+            }
+
+            if !ready!(self.poll_fill(cx))? {
+                return Poll::Ready(Err(match self.state {
+                    State::Preamble => Error::MissingFirstBoundary,
+                    State::Headers if twoway::find_bytes(&self.buf, &self.delimiter).is_some() => {
+                        Error::MissingHeaderTerminator
+                    }
+                    _ => Error::MissingClosingBoundary,
+                }));
+            }
+        }
+    }
+
+    fn poll_data(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        loop {
+            if !matches!(self.state, State::Body) {
+                return Poll::Ready(None);
+            }
+
+            if let Some(pos) = twoway::find_bytes(&self.buf, &self.delimiter) {
+                let data = self.buf.drain(..pos).collect::<Vec<_>>();
+                self.buf.drain(..self.delimiter.len());
+                self.state = State::Boundary;
+
+                if data.is_empty() {
+                    return Poll::Ready(None);
+                }
+
+                return Poll::Ready(Some(self.check_part_size(data)));
+            }
+
+            // keep the tail which can be the beginning of the delimiter
+            let keep = self.delimiter.len() - 1;
+
+            if self.buf.len() > keep {
+                let data = self.buf.drain(..self.buf.len() - keep).collect::<Vec<_>>();
+
+                return Poll::Ready(Some(self.check_part_size(data)));
+            }
+
+            match ready!(self.poll_fill(cx)) {
+                Ok(true) => {}
+                Ok(false) => return Poll::Ready(Some(Err(Error::MissingClosingBoundary))),
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            }
+        }
+    }
+
+    fn check_part_size(&mut self, data: Vec<u8>) -> Result<Bytes, Error> {
+        self.part_size += data.len();
+        self.limits
+            .check_part_size(self.part_name.as_deref(), self.part_size)?;
+
+        Ok(data.into())
+    }
+}
+
+/// A part of [`MultipartStream`], yields body of the part as it arrives
+pub struct Part<'a> {
+    headers: HeaderMap,
+    content_disposition: ContentDisposition,
+    stream: &'a mut MultipartStream,
+}
+
+impl Part<'_> {
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.content_disposition.name.as_deref()
+    }
+
+    pub fn file_name(&self) -> Option<&str> {
+        self.content_disposition.file_name.as_deref()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+    }
+
+    pub async fn chunk(&mut self) -> Option<Result<Bytes, Error>> {
+        poll_fn(|cx| self.stream.poll_data(cx)).await
+    }
+
+    /// Reads the rest of the part into memory
+    pub async fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+
+        while let Some(chunk) = self.chunk().await {
+            buf.extend_from_slice(&chunk?);
+        }
+
+        Ok(buf)
+    }
+
+    /// Reads the rest of the part into memory
+    pub async fn field(self) -> Result<Field, Error> {
+        let headers = self.headers.clone();
+
+        Ok(Field::new(headers, self.bytes().await?))
+    }
+}
+
+impl Stream for Part<'_> {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().stream.poll_data(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::{header, Request, StatusCode};
+    use hyper::Body;
+
+    use super::MultipartStream;
+    use crate::{multipart::Error, BodyLimits};
+
+    const BOUNDARY: &str = "abhjdahkdhfsikldhjfliawefrkhkahskda";
+
+    fn fixture() -> String {
+        format!(
+            r#"preamble
+--{BOUNDARY}
+Content-Disposition: form-data; name="text"
+
+hello
+--{BOUNDARY}
+Content-Disposition: form-data; name="file"; filename="a.txt"
+Content-Type: text/plain
+
+--{BOUNDARY}-- is not a boundary
+second line
+
+--{BOUNDARY}--
+epilogue"#
+        )
+        .replace('\n', "\r\n")
+    }
+
+    async fn parse(chunk_size: usize) -> Vec<(String, Vec<u8>)> {
+        let (mut sender, body) = Body::channel();
+
+        let chunks = fixture()
+            .into_bytes()
+            .chunks(chunk_size)
+            .map(|x| x.to_vec())
+            .collect::<Vec<_>>();
+
+        tokio::spawn(async move {
+            for chunk in chunks {
+                sender.send_data(chunk.into()).await.unwrap();
+            }
+        });
+
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(body)
+            .unwrap();
+
+        let mut multipart = MultipartStream::new(&mut request).unwrap();
+        let mut parts = Vec::new();
+
+        while let Some(mut part) = multipart.next_part().await.unwrap() {
+            let content_disposition = part
+                .headers()
+                .get(header::CONTENT_DISPOSITION)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_owned();
+
+            let mut buf = Vec::new();
+
+            while let Some(chunk) = part.chunk().await {
+                buf.extend_from_slice(&chunk.unwrap());
+            }
+
+            parts.push((content_disposition, buf));
+        }
+
+        parts
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream() {
+        for chunk_size in [1, 2, 3, 7, 40, 1024] {
+            let parts = parse(chunk_size).await;
+
+            assert_eq!(parts.len(), 2);
+            assert_eq!(parts[0].0, r#"form-data; name="text""#);
+            assert_eq!(parts[0].1, b"hello");
+            assert_eq!(parts[1].0, r#"form-data; name="file"; filename="a.txt""#);
+            assert_eq!(
+                parts[1].1,
+                format!("--{BOUNDARY}-- is not a boundary\r\nsecond line\r\n").as_bytes()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream_skip_part() {
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/mixed; boundary={BOUNDARY}"),
+            )
+            .body(fixture().into())
+            .unwrap();
+
+        let mut multipart = MultipartStream::new(&mut request).unwrap();
+
+        multipart.next_part().await.unwrap().unwrap();
+
+        let part = multipart.next_part().await.unwrap().unwrap();
+
+        assert!(part.headers().contains_key(header::CONTENT_TYPE));
+        assert!(part.bytes().await.unwrap().ends_with(b"second line\r\n"));
+
+        assert!(multipart.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream_collect_form() {
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(fixture().into())
+            .unwrap();
+
+        let form = MultipartStream::new(&mut request)
+            .unwrap()
+            .collect_form()
+            .await
+            .unwrap();
+
+        assert_eq!(form.text("text"), Some("hello"));
+
+        let file = form.file("file").unwrap();
+
+        assert_eq!(file.file_name(), Some("a.txt"));
+        assert_eq!(file.content_type(), Some("text/plain"));
+    }
+
+    #[tokio::test]
+    async fn test_multipart_stream_timeout() {
+        let (mut sender, body) = Body::channel();
+
+        tokio::spawn(async move {
+            sender
+                .send_data(format!("--{BOUNDARY}\r\n").into())
+                .await
+                .unwrap();
+
+            // keeps the body open
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let mut request = Request::builder()
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .extension(BodyLimits::new().timeout(Duration::from_millis(10)))
+            .body(body)
+            .unwrap();
+
+        let err = MultipartStream::new(&mut request)
+            .unwrap()
+            .next_part()
+            .await
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::Timeout));
+        assert_eq!(err.status_code(), StatusCode::REQUEST_TIMEOUT);
+    }
+}
@@ -0,0 +1,546 @@
+use http::{header, HeaderValue, Request, Response, StatusCode};
+use hyper::Body;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    body_parser::BoxError,
+    headers::{Accept, Header, MediaType},
+    IntoResponse, SetResponse,
+};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Not acceptable, available: {}", .available.iter().join(", "))]
+pub struct NotAcceptable {
+    pub available: Vec<MediaType>,
+}
+
+impl NotAcceptable {
+    /// 406
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::NOT_ACCEPTABLE
+    }
+}
+
+/// The best of the available media types for `Accept` of the request, RFC 9110 12.5.1
+///
+/// A media type gets the quality of the most specific range matching it, ties go to the
+/// earlier one in `available`. Without a valid `Accept`, anything is acceptable.
+///
+/// ```no_run
+/// # use http::Request;
+/// # use http_util::{negotiate, MediaType, NotAcceptable};
+/// # fn handle(request: Request<()>) -> Result<(), NotAcceptable> {
+/// let json = MediaType::new("application", "json");
+/// let csv = MediaType::new("text", "csv");
+///
+/// match negotiate(&request, &[json, csv])?.subtype() {
+///     "csv" => { /* writes CSV */ }
+///     _ => { /* writes JSON */ }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn negotiate<'a, B>(
+    request: &Request<B>,
+    available: &'a [MediaType],
+) -> Result<&'a MediaType, NotAcceptable> {
+    let not_acceptable = || NotAcceptable {
+        available: available.to_vec(),
+    };
+
+    let accept = match Accept::from_headers(request.headers()) {
+        Ok(accept) => accept,
+        Err(_) => return available.first().ok_or_else(not_acceptable),
+    };
+
+    available
+        .iter()
+        .map(|x| (accept.quality_of(x), x))
+        .filter(|(quality, _)| *quality > 0)
+        // the last of the maximums is the earliest one in reverse
+        .rev()
+        .max_by_key(|(quality, _)| *quality)
+        .map(|(_, x)| x)
+        .ok_or_else(not_acceptable)
+}
+
+/// A serialization format of [`Negotiated`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// A row per element of a sequence, with the header from the fields of the first one.
+    /// Rows are structs, tuples or sequences, maps are not supported.
+    #[cfg(feature = "csv")]
+    Csv,
+}
+
+impl Format {
+    /// Every enabled format, JSON first
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Json,
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack,
+            #[cfg(feature = "cbor")]
+            Self::Cbor,
+            #[cfg(feature = "csv")]
+            Self::Csv,
+        ]
+    }
+
+    /// The first one is sent if the client accepts any
+    pub fn media_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["application/json"],
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => &[
+                "application/msgpack",
+                "application/x-msgpack",
+                "application/vnd.msgpack",
+            ],
+            #[cfg(feature = "cbor")]
+            Self::Cbor => &["application/cbor"],
+            #[cfg(feature = "csv")]
+            Self::Csv => &["text/csv"],
+        }
+    }
+
+    pub fn serialize<T>(&self, value: &T) -> Result<Vec<u8>, BoxError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Self::Msgpack => Ok(rmp_serde::to_vec_named(value)?),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::ser::into_writer(value, &mut buf)?;
+
+                Ok(buf)
+            }
+            #[cfg(feature = "csv")]
+            Self::Csv => to_csv(value),
+        }
+    }
+}
+
+/// Writes each element of a sequence as a row by `csv::Writer::serialize`, the header is from
+/// the field names of the first struct
+#[cfg(feature = "csv")]
+fn to_csv<T>(value: &T) -> Result<Vec<u8>, BoxError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut rows = csv_rows::Rows {
+        writer: &mut writer,
+        single: false,
+    };
+
+    if let Err(e) = value.serialize(&mut rows) {
+        if !rows.single {
+            return Err(e.into());
+        }
+
+        // not a sequence, a single row
+        writer.serialize(value)?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+#[cfg(feature = "csv")]
+mod csv_rows {
+    use serde::{
+        ser::{self, Error as _, Impossible},
+        Serialize,
+    };
+
+    /// Serializer of the top level which passes elements of a sequence to the writer,
+    /// `single` is set for the other values
+    pub(super) struct Rows<'a> {
+        pub writer: &'a mut csv::Writer<Vec<u8>>,
+        pub single: bool,
+    }
+
+    impl Rows<'_> {
+        fn single<T>(&mut self) -> Result<T, csv::Error> {
+            self.single = true;
+
+            Err(csv::Error::custom("not a sequence"))
+        }
+    }
+
+    macro_rules! single {
+        ($($method:ident($($arg:ty),*) -> $ty:ty;)*) => {
+            $(
+                fn $method(self, $(_: $arg),*) -> Result<$ty, Self::Error> {
+                    self.single()
+                }
+            )*
+        };
+    }
+
+    impl<'a, 'b> ser::Serializer for &'a mut Rows<'b> {
+        type Ok = ();
+        type Error = csv::Error;
+        type SerializeSeq = Self;
+        type SerializeTuple = Self;
+        type SerializeTupleStruct = Impossible<(), csv::Error>;
+        type SerializeTupleVariant = Impossible<(), csv::Error>;
+        type SerializeMap = Impossible<(), csv::Error>;
+        type SerializeStruct = Impossible<(), csv::Error>;
+        type SerializeStructVariant = Impossible<(), csv::Error>;
+
+        single! {
+            serialize_bool(bool) -> ();
+            serialize_i8(i8) -> ();
+            serialize_i16(i16) -> ();
+            serialize_i32(i32) -> ();
+            serialize_i64(i64) -> ();
+            serialize_u8(u8) -> ();
+            serialize_u16(u16) -> ();
+            serialize_u32(u32) -> ();
+            serialize_u64(u64) -> ();
+            serialize_f32(f32) -> ();
+            serialize_f64(f64) -> ();
+            serialize_char(char) -> ();
+            serialize_str(&str) -> ();
+            serialize_bytes(&[u8]) -> ();
+            serialize_none() -> ();
+            serialize_unit() -> ();
+            serialize_unit_struct(&'static str) -> ();
+            serialize_unit_variant(&'static str, u32, &'static str) -> ();
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+            serialize_struct_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeStructVariant;
+        }
+
+        fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.single()
+        }
+
+        fn serialize_seq(self, _: Option<usize>) -> Result<Self, Self::Error> {
+            Ok(self)
+        }
+
+        fn serialize_tuple(self, _: usize) -> Result<Self, Self::Error> {
+            Ok(self)
+        }
+    }
+
+    impl ser::SerializeSeq for &mut Rows<'_> {
+        type Ok = ();
+        type Error = csv::Error;
+
+        fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.writer.serialize(value)
+        }
+
+        fn end(self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl ser::SerializeTuple for &mut Rows<'_> {
+        type Ok = ();
+        type Error = csv::Error;
+
+        fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.writer.serialize(value)
+        }
+
+        fn end(self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+}
+
+/// Serializes in the format negotiated with `Accept` of the request
+///
+/// ```no_run
+/// # use http::{Request, Response};
+/// # use hyper::Body;
+/// # use http_util::{BoxError, Negotiated};
+/// # fn handle(request: Request<Body>, users: Vec<String>) -> Result<(), BoxError> {
+/// # let mut response = Response::new(Body::empty());
+/// let negotiated = Negotiated::new(&request, users)?;
+///
+/// negotiated.set_response(&mut response)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Negotiated<T> {
+    value: T,
+    format: Format,
+    media_type: &'static str,
+}
+
+impl<T> Negotiated<T>
+where
+    T: Serialize,
+{
+    /// Among [`Format::all`]
+    pub fn new<B>(request: &Request<B>, value: T) -> Result<Self, NotAcceptable> {
+        Self::with_formats(request, &Format::all(), value)
+    }
+
+    /// Earlier formats win ties
+    pub fn with_formats<B>(
+        request: &Request<B>,
+        formats: &[Format],
+        value: T,
+    ) -> Result<Self, NotAcceptable> {
+        let candidates = formats
+            .iter()
+            .flat_map(|format| format.media_types().iter().map(move |x| (*format, *x)))
+            .collect::<Vec<_>>();
+
+        let available = candidates
+            .iter()
+            .map(|(_, x)| x.parse::<MediaType>().expect("static media type"))
+            .collect::<Vec<_>>();
+
+        let chosen = negotiate(request, &available)?;
+        let i = available.iter().position(|x| x == chosen).unwrap_or(0);
+        let (format, media_type) = candidates[i];
+
+        Ok(Self {
+            value,
+            format,
+            media_type,
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// As accepted, one of [`Format::media_types`]
+    pub fn media_type(&self) -> &'static str {
+        self.media_type
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Sets `Content-Type`, `Vary: Accept` and body
+    pub fn set_response<R, B>(&self, response: &mut R) -> Result<(), BoxError>
+    where
+        R: SetResponse<B, Error = http::Error>,
+        B: From<Vec<u8>>,
+    {
+        let buf = self.format.serialize(&self.value)?;
+
+        response.insert_headers(std::iter::once((
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(self.media_type),
+        )));
+        response.set_header(header::VARY, "accept")?;
+        response.set_body(buf.into());
+
+        Ok(())
+    }
+}
+
+/// Responds 500 if the serialization fails
+impl<T> IntoResponse for Negotiated<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+
+        match self.set_response(&mut response) {
+            Ok(()) => response,
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, Request, StatusCode};
+    use hyper::body::to_bytes;
+    use serde::Serialize;
+
+    use super::{negotiate, Format, Negotiated};
+    use crate::{headers::MediaType, IntoResponse};
+
+    fn request(accept: Option<&str>) -> Request<()> {
+        let mut builder = Request::get("/");
+
+        if let Some(accept) = accept {
+            builder = builder.header(header::ACCEPT, accept);
+        }
+
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let json = MediaType::new("application", "json");
+        let html = MediaType::new("text", "html");
+        let plain = MediaType::new("text", "plain");
+        let available = [json.clone(), html.clone(), plain.clone()];
+
+        assert_eq!(negotiate(&request(None), &available), Ok(&json));
+        assert_eq!(negotiate(&request(Some("*/*")), &available), Ok(&json));
+        assert_eq!(
+            negotiate(&request(Some("text/*, application/json;q=0.5")), &available),
+            Ok(&html)
+        );
+        // the more specific range decides
+        assert_eq!(
+            negotiate(&request(Some("text/*;q=0.9, text/html;q=0.1")), &available),
+            Ok(&plain)
+        );
+        assert_eq!(
+            negotiate(
+                &request(Some("*/*;q=0.1, application/json;q=0")),
+                &available
+            ),
+            Ok(&html)
+        );
+
+        let e = negotiate(&request(Some("image/png")), &available).unwrap_err();
+
+        assert_eq!(e.status_code(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(
+            e.to_string(),
+            "Not acceptable, available: application/json, text/html, text/plain"
+        );
+    }
+
+    #[derive(Debug, Serialize)]
+    struct Row {
+        name: &'static str,
+        age: u8,
+    }
+
+    #[tokio::test]
+    async fn test_negotiated() {
+        let rows = || vec![Row { name: "a", age: 1 }];
+
+        let response = Negotiated::new(&request(Some("application/*")), rows())
+            .unwrap()
+            .into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(response.headers()[header::VARY], "accept");
+
+        let mut existing = http::Response::builder()
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Vec::new())
+            .unwrap();
+
+        Negotiated::new(&request(None), rows())
+            .unwrap()
+            .set_response(&mut existing)
+            .unwrap();
+
+        assert_eq!(
+            existing
+                .headers()
+                .get_all(header::CONTENT_TYPE)
+                .iter()
+                .collect::<Vec<_>>(),
+            ["application/json"]
+        );
+        assert_eq!(
+            to_bytes(response.into_body()).await.unwrap(),
+            r#"[{"name":"a","age":1}]"#
+        );
+
+        let e = Negotiated::with_formats(&request(Some("text/html")), &[Format::Json], rows())
+            .unwrap_err();
+
+        assert_eq!(e.status_code(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_negotiated_msgpack() {
+        let negotiated =
+            Negotiated::new(&request(Some("application/x-msgpack")), vec![1, 2]).unwrap();
+
+        assert_eq!(negotiated.format(), Format::Msgpack);
+        assert_eq!(negotiated.media_type(), "application/x-msgpack");
+    }
+
+    #[cfg(feature = "csv")]
+    #[tokio::test]
+    async fn test_negotiated_csv() {
+        let rows = vec![
+            Row { name: "a", age: 1 },
+            Row {
+                name: "b, c",
+                age: 2,
+            },
+        ];
+
+        let response = Negotiated::new(&request(Some("text/csv, */*;q=0.1")), rows)
+            .unwrap()
+            .into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            to_bytes(response.into_body()).await.unwrap(),
+            "name,age\na,1\n\"b, c\",2\n"
+        );
+        // in the order of the fields, a single row and rows without a header
+        assert_eq!(
+            Format::Csv.serialize(&Row { name: "a", age: 1 }).unwrap(),
+            b"name,age\na,1\n"
+        );
+        assert_eq!(
+            Format::Csv.serialize(&[("a", 1), ("b", 2)]).unwrap(),
+            b"a,1\nb,2\n"
+        );
+        assert!(Format::Csv
+            .serialize(&[std::collections::HashMap::from([("a", 1)])])
+            .is_err());
+    }
+}
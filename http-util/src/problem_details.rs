@@ -0,0 +1,294 @@
+use http::{header, HeaderValue, Response, StatusCode};
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{body_parser, multipart, IntoResponse, NotAcceptable, SetResponse};
+
+pub const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem Details for HTTP APIs, RFC 7807
+///
+/// ```no_run
+/// # use http::{Response, StatusCode};
+/// # use http_util::ProblemDetails;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut response = Response::new(hyper::Body::empty());
+/// ProblemDetails::new(StatusCode::FORBIDDEN)
+///     .type_uri("https://example.com/probs/out-of-credit")
+///     .detail("Your current balance is 30, but that costs 50.")
+///     .extension("balance", 30)
+///     .set_response(&mut response)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type", default = "about_blank")]
+    pub type_uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension members
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_owned()
+}
+
+impl ProblemDetails {
+    /// `about:blank` with the reason phrase of the status as the title
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            type_uri: about_blank(),
+            title: status.canonical_reason().map(ToOwned::to_owned),
+            status: Some(status.as_u16()),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    pub fn type_uri(mut self, type_uri: impl Into<String>) -> Self {
+        self.type_uri = type_uri.into();
+
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title.replace(title.into());
+
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail.replace(detail.into());
+
+        self
+    }
+
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance.replace(instance.into());
+
+        self
+    }
+
+    /// Ignored if the value is not serializable
+    pub fn extension(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.extensions.insert(key.into(), value);
+        }
+
+        self
+    }
+
+    /// 500 if `status` is absent or invalid
+    pub fn status_code(&self) -> StatusCode {
+        self.status
+            .and_then(|x| StatusCode::from_u16(x).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // keys of the map are strings, can not fail
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+
+    /// Sets status, `Content-Type: application/problem+json` and body
+    pub fn set_response<R, B>(&self, response: &mut R) -> Result<(), http::Error>
+    where
+        R: SetResponse<B, Error = http::Error>,
+        B: From<Vec<u8>>,
+    {
+        response.set_status(self.status_code())?;
+        // replaces `Content-Type` of the response being turned into a problem
+        response.insert_headers(std::iter::once((
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+        )));
+        response.set_body(self.to_bytes().into());
+
+        Ok(())
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+
+        // the status code is valid and the header is static
+        let _ = self.set_response(&mut response);
+
+        response
+    }
+}
+
+impl From<body_parser::Error> for ProblemDetails {
+    fn from(e: body_parser::Error) -> Self {
+        let problem = Self::new(e.status_code()).detail(e.to_string());
+
+        match &e {
+            body_parser::Error::JsonDeserialize(e) => problem
+                .extension("path", e.path())
+                .extension("line", e.line())
+                .extension("column", e.column()),
+            _ => problem,
+        }
+    }
+}
+
+impl From<multipart::Error> for ProblemDetails {
+    fn from(e: multipart::Error) -> Self {
+        Self::new(e.status_code()).detail(e.to_string())
+    }
+}
+
+/// Lists the available media types as `available`
+impl From<NotAcceptable> for ProblemDetails {
+    fn from(e: NotAcceptable) -> Self {
+        let available = e
+            .available
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        Self::new(e.status_code())
+            .detail(e.to_string())
+            .extension("available", available)
+    }
+}
+
+/// 422
+#[cfg(feature = "validate")]
+impl From<util::validate::string::Error> for ProblemDetails {
+    fn from(e: util::validate::string::Error) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY).detail(e.to_string())
+    }
+}
+
+/// 422
+#[cfg(feature = "validate")]
+impl<T> From<util::validate::number::Error<T>> for ProblemDetails
+where
+    T: util::validate::Num,
+{
+    fn from(e: util::validate::number::Error<T>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY).detail(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header, Request, Response, StatusCode};
+    use hyper::Body;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::ProblemDetails;
+    use crate::{multipart, BodyParser, MediaType, NotAcceptable};
+
+    #[test]
+    fn test_problem_details() {
+        let problem = ProblemDetails::new(StatusCode::FORBIDDEN)
+            .type_uri("https://example.com/probs/out-of-credit")
+            .detail("Your current balance is 30, but that costs 50.")
+            .instance("/account/12345/msgs/abc")
+            .extension("balance", 30);
+
+        let mut response = Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Vec::new())
+            .unwrap();
+
+        problem.set_response(&mut response).unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response
+                .headers()
+                .get_all(header::CONTENT_TYPE)
+                .iter()
+                .collect::<Vec<_>>(),
+            ["application/problem+json"]
+        );
+
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+
+        assert_eq!(
+            body,
+            json!({
+                "type": "https://example.com/probs/out-of-credit",
+                "title": "Forbidden",
+                "status": 403,
+                "detail": "Your current balance is 30, but that costs 50.",
+                "instance": "/account/12345/msgs/abc",
+                "balance": 30
+            })
+        );
+
+        let parsed: ProblemDetails = serde_json::from_value(body).unwrap();
+
+        assert_eq!(parsed, problem);
+    }
+
+    #[tokio::test]
+    async fn test_problem_details_from_errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Payload {
+            age: u8,
+        }
+
+        let mut request = Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"age": "twelve"}"#))
+            .unwrap();
+
+        let e = BodyParser::<Payload>::body_parse(&mut request)
+            .await
+            .unwrap_err();
+
+        let problem = ProblemDetails::from(e);
+
+        assert_eq!(problem.status, Some(400));
+        assert_eq!(problem.extensions["path"], "age");
+
+        let problem = ProblemDetails::from(multipart::Error::NotMultipart);
+
+        assert_eq!(problem.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(problem.type_uri, "about:blank");
+
+        let problem = ProblemDetails::from(NotAcceptable {
+            available: vec![MediaType::new("application", "json")],
+        });
+
+        assert_eq!(problem.status_code(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(problem.extensions["available"], json!(["application/json"]));
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_problem_details_from_validate() {
+        use util::validate::{ValidatorNumberExt, ValidatorStringExt};
+
+        let e = "@".validate().email().take().unwrap_err();
+        let problem = ProblemDetails::from(e);
+
+        assert_eq!(problem.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem.detail.as_deref(), Some(r#""@" is not email"#));
+
+        let e = 3.validate().max(2).take().unwrap_err();
+
+        assert_eq!(
+            ProblemDetails::from(e).detail.as_deref(),
+            Some("3 more than 2")
+        );
+    }
+}
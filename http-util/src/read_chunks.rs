@@ -1,8 +1,96 @@
+use std::time::Duration;
+
+use http::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error<E> {
+    #[error("Payload too large: more than {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("Timeout")]
+    Timeout,
+    #[error("Body: {0}")]
+    Body(E),
+}
+
+impl<E> Error<E> {
+    /// 413, 408 or 400
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Timeout => StatusCode::REQUEST_TIMEOUT,
+            Self::Body(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Shared by [`crate::BodyParser`], [`crate::Multipart`] and [`crate::MultipartStream`] through
+/// extensions of the request
+///
+/// Unlimited by default
+#[derive(Debug, Default, Clone)]
+pub struct BodyLimits {
+    /// Bytes
+    pub max_bytes: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+impl BodyLimits {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes.replace(max_bytes);
+
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout.replace(timeout);
+
+        self
+    }
+}
+
 #[async_trait::async_trait]
 pub trait ReadChunks {
     type Error;
 
     async fn read_chunks(&mut self) -> Result<Vec<u8>, Self::Error>;
+
+    async fn read_chunks_limited(
+        &mut self,
+        max_bytes: usize,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error<Self::Error>>
+    where
+        Self: Send,
+    {
+        let limits = BodyLimits::new().max_bytes(max_bytes).timeout(timeout);
+
+        self.read_chunks_with(&limits).await
+    }
+
+    /// The default checks `max_bytes` once the whole body is read, implementors reading in
+    /// chunks should override it to stop early
+    async fn read_chunks_with(&mut self, limits: &BodyLimits) -> Result<Vec<u8>, Error<Self::Error>>
+    where
+        Self: Send,
+    {
+        let read = async { self.read_chunks().await.map_err(Error::Body) };
+
+        let buf = match limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, read)
+                .await
+                .map_err(|_| Error::Timeout)??,
+            None => read.await?,
+        };
+
+        match limits.max_bytes {
+            Some(max_bytes) if buf.len() > max_bytes => Err(Error::PayloadTooLarge(max_bytes)),
+            _ => Ok(buf),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -20,4 +108,96 @@ impl ReadChunks for hyper::Body {
 
         Ok(chunks.concat())
     }
+
+    async fn read_chunks_with(
+        &mut self,
+        limits: &BodyLimits,
+    ) -> Result<Vec<u8>, Error<Self::Error>> {
+        let max_bytes = limits.max_bytes;
+
+        let read = async {
+            let mut buf = Vec::new();
+
+            while let Some(chunk) = hyper::body::HttpBody::data(self).await {
+                let chunk = chunk.map_err(Error::Body)?;
+
+                match max_bytes {
+                    Some(max_bytes) if buf.len() + chunk.len() > max_bytes => {
+                        return Err(Error::PayloadTooLarge(max_bytes))
+                    }
+                    _ => buf.extend_from_slice(&chunk),
+                }
+            }
+
+            Ok(buf)
+        };
+
+        match limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, read)
+                .await
+                .map_err(|_| Error::Timeout)?,
+            None => read.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use hyper::Body;
+
+    use super::{BodyLimits, Error, ReadChunks};
+
+    #[tokio::test]
+    async fn test_read_chunks_limited() {
+        let buf = Body::from("0123456789")
+            .read_chunks_limited(10, Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert_eq!(buf, b"0123456789");
+
+        let r = Body::from("0123456789")
+            .read_chunks_limited(9, Duration::from_secs(1))
+            .await;
+
+        assert!(matches!(r, Err(Error::PayloadTooLarge(9))));
+
+        // the sender never finishes the body
+        let (_sender, mut body) = Body::channel();
+
+        let r = body
+            .read_chunks_limited(10, Duration::from_millis(10))
+            .await;
+
+        assert!(matches!(r, Err(Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_read_chunks_with_default() {
+        struct Bytes(&'static [u8]);
+
+        #[async_trait::async_trait]
+        impl ReadChunks for Bytes {
+            type Error = std::convert::Infallible;
+
+            async fn read_chunks(&mut self) -> Result<Vec<u8>, Self::Error> {
+                Ok(self.0.to_vec())
+            }
+        }
+
+        let buf = Bytes(b"0123456789")
+            .read_chunks_with(&BodyLimits::new().max_bytes(10))
+            .await
+            .unwrap();
+
+        assert_eq!(buf, b"0123456789");
+
+        let r = Bytes(b"0123456789")
+            .read_chunks_limited(9, Duration::from_secs(1))
+            .await;
+
+        assert!(matches!(r, Err(Error::PayloadTooLarge(9))));
+    }
 }
@@ -1,4 +1,6 @@
-use http::{header::HeaderName, HeaderValue, Response, StatusCode};
+use http::{header::HeaderName, HeaderMap, HeaderValue, Response, StatusCode};
+
+use crate::headers::{Header, HeaderError};
 
 pub trait SetResponse<B> {
     type Error;
@@ -17,6 +19,32 @@ pub trait SetResponse<B> {
 
     fn set_headers(&mut self, headers: impl Iterator<Item = (HeaderName, HeaderValue)>);
 
+    /// Replaces the existing values of the same names, unlike [`SetResponse::set_headers`],
+    /// the last one is kept if a name is repeated
+    ///
+    /// The default passes the last value of each name to [`SetResponse::set_headers`],
+    /// implementors that can hold headers already should override it to drop those.
+    fn insert_headers(&mut self, headers: impl Iterator<Item = (HeaderName, HeaderValue)>) {
+        let mut header_map = HeaderMap::new();
+
+        for (key, value) in headers {
+            header_map.insert(key, value);
+        }
+
+        self.set_headers(
+            header_map
+                .into_iter()
+                .filter_map(|(key, value)| Some((key?, value))),
+        );
+    }
+
+    /// Replaces the existing values
+    fn set_typed_header<H: Header>(&mut self, header: &H) -> Result<(), HeaderError> {
+        self.insert_headers(std::iter::once((H::name(), header.to_header_value()?)));
+
+        Ok(())
+    }
+
     fn set_body(&mut self, body: B);
 }
 
@@ -55,6 +83,14 @@ impl<B> SetResponse<B> for Response<B> {
         }
     }
 
+    fn insert_headers(&mut self, headers: impl Iterator<Item = (HeaderName, HeaderValue)>) {
+        let header_map = self.headers_mut();
+
+        for (key, value) in headers {
+            header_map.insert(key, value);
+        }
+    }
+
     fn set_body(&mut self, body: B) {
         *self.body_mut() = body;
     }
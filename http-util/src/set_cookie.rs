@@ -1,20 +1,92 @@
-use std::collections::HashMap;
+use std::{fmt::Display, str::FromStr};
 
+use chrono::{DateTime, NaiveDate, Utc};
 use http::{
     header::{self, HeaderName},
     HeaderMap, HeaderValue,
 };
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    cookie::{decode_json, encode_json, parse_value, CookieValueError},
+    headers::fmt_http_date,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Browsers require `Secure` as well, which is always written with it
+    None,
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Strict => write!(f, "Strict"),
+            Self::Lax => write!(f, "Lax"),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+impl FromStr for SameSite {
+    type Err = ();
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        match st.to_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Low => write!(f, "Low"),
+            Self::Medium => write!(f, "Medium"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = ();
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        match st.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(()),
+        }
+    }
+}
 
 #[cfg_attr(test, derive(PartialEq))]
 #[derive(Default, Clone, Debug)]
 pub struct SetCookieOptions {
     pub http_only: bool,
     pub secure: bool,
-    // expires: ,
+    pub expires: Option<DateTime<Utc>>,
     /// Seconds
     pub max_age: Option<i64>,
     pub domain: Option<String>,
     pub path: Option<String>,
+    pub same_site: Option<SameSite>,
+    /// CHIPS, requires `Secure` which is always written with it
+    pub partitioned: bool,
+    pub priority: Option<Priority>,
 }
 
 impl SetCookieOptions {
@@ -28,9 +100,12 @@ impl SetCookieOptions {
         st.starts_with("max-age=")
             || st.starts_with("domain=")
             || st.starts_with("path=")
-            // || st.starts_with("expires=")
+            || st.starts_with("expires=")
+            || st.starts_with("samesite=")
+            || st.starts_with("priority=")
             || st.eq("httponly")
             || st.eq("secure")
+            || st.eq("partitioned")
     }
 
     pub fn http_only(mut self, http_only: bool) -> Self {
@@ -63,36 +138,72 @@ impl SetCookieOptions {
 
         self
     }
+
+    pub fn expires(mut self, expires: DateTime<Utc>) -> Self {
+        self.expires.replace(expires);
+
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site.replace(same_site);
+
+        self
+    }
+
+    pub fn partitioned(mut self, partitioned: bool) -> Self {
+        self.partitioned = partitioned;
+
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority.replace(priority);
+
+        self
+    }
 }
 
+/// Attribute names are case-insensitive, values are kept as sent
 impl<'a> From<Vec<&'a str>> for SetCookieOptions {
     fn from(xs: Vec<&'a str>) -> Self {
-        let mut options = SetCookieOptions {
-            domain: None,
-            max_age: None,
-            path: None,
-            http_only: false,
-            secure: false,
-        };
+        let mut options = SetCookieOptions::new();
 
-        for st in xs.iter().map(|st| st.to_lowercase()) {
-            if st.starts_with("domain=") {
-                let domain = st.split('=').nth(1).unwrap_or_default();
-                options.domain.replace(domain.to_string());
-            } else if st.starts_with("max-age=") {
-                let max_age = st
-                    .split('=')
-                    .nth(1)
-                    .and_then(|x| x.parse().ok())
-                    .unwrap_or_default();
-                options.max_age.replace(max_age);
-            } else if st.starts_with("path=") {
-                let path = st.split('=').nth(1).unwrap_or("/");
-                options.path.replace(path.to_string());
-            } else if st.starts_with("httponly") {
-                options.http_only = true;
-            } else if st.starts_with("secure") {
-                options.secure = true;
+        for attribute in xs {
+            let (name, value) = match attribute.split_once('=') {
+                Some((name, value)) => (name.trim(), Some(value.trim())),
+                None => (attribute.trim(), None),
+            };
+
+            match name.to_lowercase().as_str() {
+                "domain" => {
+                    options
+                        .domain
+                        .replace(value.unwrap_or_default().to_string());
+                }
+                // an invalid value is ignored, RFC 6265 5.2.2
+                "max-age" => {
+                    if let Some(max_age) = value
+                        .filter(|x| !x.starts_with('+'))
+                        .and_then(|x| x.parse().ok())
+                    {
+                        options.max_age.replace(max_age);
+                    }
+                }
+                "path" => {
+                    options.path.replace(value.unwrap_or("/").to_string());
+                }
+                "expires" => {
+                    if let Some(expires) = value.and_then(parse_cookie_date) {
+                        options.expires.replace(expires);
+                    }
+                }
+                "samesite" => options.same_site = value.and_then(|x| x.parse().ok()),
+                "priority" => options.priority = value.and_then(|x| x.parse().ok()),
+                "httponly" => options.http_only = true,
+                "secure" => options.secure = true,
+                "partitioned" => options.partitioned = true,
+                _ => {}
             }
         }
 
@@ -100,10 +211,111 @@ impl<'a> From<Vec<&'a str>> for SetCookieOptions {
     }
 }
 
+/// `Expires` of RFC 6265 5.1.1, lenient enough for `Thu, 01-Jan-1970 00:00:01 GMT` and the like
+fn parse_cookie_date(st: &str) -> Option<DateTime<Utc>> {
+    let is_delimiter = |c: char| matches!(c, '\x09' | '\x20'..='\x2f' | '\x3b'..='\x40' | '\x5b'..='\x60' | '\x7b'..='\x7e');
+
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+
+    for token in st.split(is_delimiter).filter(|x| !x.is_empty()) {
+        if time.is_none() {
+            if let Some(x) = parse_cookie_time(token) {
+                time.replace(x);
+                continue;
+            }
+        }
+
+        if day.is_none() {
+            if let Some(x) = leading_digits(token, 1, 2) {
+                day.replace(x);
+                continue;
+            }
+        }
+
+        if month.is_none() {
+            if let Some(x) = parse_cookie_month(token) {
+                month.replace(x);
+                continue;
+            }
+        }
+
+        if year.is_none() {
+            if let Some(x) = leading_digits(token, 2, 4) {
+                year.replace(x);
+                continue;
+            }
+        }
+    }
+
+    let (hour, min, sec) = time?;
+    let year = match year? {
+        x @ 70..=99 => x + 1900,
+        x @ 0..=69 => x + 2000,
+        x => x,
+    };
+
+    if year < 1601 || hour > 23 || min > 59 || sec > 59 {
+        return None;
+    }
+
+    NaiveDate::from_ymd_opt(year as i32, month?, day?)?
+        .and_hms_opt(hour, min, sec)
+        .map(|x| x.and_utc())
+}
+
+/// `hh:mm:ss` of 1 or 2 digits each, followed by anything but a digit
+fn parse_cookie_time(token: &str) -> Option<(u32, u32, u32)> {
+    let mut fields = token.splitn(3, ':');
+
+    let mut field = || {
+        fields
+            .next()
+            .filter(|x| x.bytes().all(|c| c.is_ascii_digit()))
+            .and_then(|x| leading_digits(x, 1, 2))
+    };
+
+    let hour = field()?;
+    let min = field()?;
+    let sec = leading_digits(fields.next()?, 1, 2)?;
+
+    Some((hour, min, sec))
+}
+
+fn parse_cookie_month(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    let prefix = token.get(..3)?.to_ascii_lowercase();
+
+    MONTHS
+        .iter()
+        .position(|x| *x == prefix)
+        .map(|x| x as u32 + 1)
+}
+
+/// `min` to `max` digits, followed by anything but a digit
+fn leading_digits(token: &str, min: usize, max: usize) -> Option<u32> {
+    let len = token.bytes().take_while(u8::is_ascii_digit).count();
+
+    if (min..=max).contains(&len) {
+        token[..len].parse().ok()
+    } else {
+        None
+    }
+}
+
+/// `Set-Cookie` headers in insertion order
+///
+/// A cookie is identified by its name, domain and path as RFC 6265 does,
+/// so the same name can be set for several paths.
 #[cfg_attr(test, derive(PartialEq))]
-#[derive(Default, Debug)]
+#[derive(Default, Clone, Debug)]
 pub struct SetCookie {
-    inner: HashMap<String, (String, SetCookieOptions)>,
+    inner: Vec<(String, String, SetCookieOptions)>,
 }
 
 impl SetCookie {
@@ -115,36 +327,123 @@ impl SetCookie {
         self.inner.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
     pub fn from_headers(headers: &HeaderMap) -> Self {
         headers
+            .get_all(header::SET_COOKIE)
             .iter()
-            .filter(|(k, _)| k == &header::SET_COOKIE)
-            .filter_map(|(_, v)| v.to_str().ok())
+            .filter_map(|v| v.to_str().ok())
             .into()
     }
 
+    /// The first one if the name is set for several domains or paths
     pub fn get(&self, key: &str) -> Option<&str> {
-        self.inner.get(key).map(|(v, _)| v.as_str())
+        self.inner
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, v, _)| v.as_str())
+    }
+
+    pub fn get_all<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a SetCookieOptions)> {
+        self.inner
+            .iter()
+            .filter(move |(k, _, _)| k == key)
+            .map(|(_, v, options)| (v.as_str(), options))
+    }
+
+    pub fn get_as<T>(&self, key: &str) -> Result<T, CookieValueError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        parse_value(key, self.get(key))
+    }
+
+    pub fn get_json<T>(&self, key: &str) -> Result<T, CookieValueError>
+    where
+        T: DeserializeOwned,
+    {
+        decode_json(key, self.get(key))
+    }
+
+    /// (name, value, options) in insertion order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &SetCookieOptions)> {
+        self.inner
+            .iter()
+            .map(|(k, v, options)| (k.as_str(), v.as_str(), options))
     }
 
+    /// Removes every cookie of the name, returns the first one
     pub fn take(&mut self, key: &str) -> Option<String> {
-        self.inner.remove(key).map(|(x, _)| x)
+        let i = self.inner.iter().position(|(k, _, _)| k == key)?;
+        let (_, value, _) = self.inner.remove(i);
+
+        self.inner.retain(|(k, _, _)| k != key);
+
+        Some(value)
     }
 
+    /// Replaces the cookie of the same name, domain and path in place
     pub fn set(
         mut self,
         key: impl Into<String>,
         value: impl Into<String>,
         options: SetCookieOptions,
     ) -> Self {
-        self.inner.insert(key.into(), (value.into(), options));
+        let key = key.into();
+        let value = value.into();
+
+        match self
+            .inner
+            .iter_mut()
+            .find(|(k, _, x)| k == &key && is_same_scope(x, &options))
+        {
+            Some(entry) => *entry = (key, value, options),
+            None => self.inner.push((key, value, options)),
+        }
 
         self
     }
 
+    /// Serializes into base64url of JSON, read by [`SetCookie::get_json`] and [`crate::Cookie::get_json`]
+    pub fn set_json<T>(
+        self,
+        key: impl Into<String>,
+        value: &T,
+        options: SetCookieOptions,
+    ) -> serde_json::Result<Self>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(self.set(key, encode_json(value)?, options))
+    }
+
+    /// Deletes the cookie on the client with an empty value, `Max-Age=0` and `Expires` of the epoch
+    ///
+    /// `options` must have the `Domain` and `Path` the cookie was set with.
+    pub fn expire(self, key: impl Into<String>, options: SetCookieOptions) -> Self {
+        let options = SetCookieOptions {
+            max_age: Some(0),
+            expires: Some(DateTime::UNIX_EPOCH),
+            ..options
+        };
+
+        self.set(key, "", options)
+    }
+
+    /// Removes every cookie of the name from the headers to send,
+    /// use [`SetCookie::expire`] to delete it on the client
     #[allow(dead_code)]
     pub fn remove(mut self, key: impl Into<String>) -> Self {
-        self.inner.remove(&key.into());
+        let key = key.into();
+
+        self.inner.retain(|(k, _, _)| k != &key);
 
         self
     }
@@ -153,20 +452,35 @@ impl SetCookie {
     pub fn iter(&self) -> impl Iterator<Item = (HeaderName, HeaderValue)> + '_ {
         self.inner
             .iter()
-            .map(|(key, (value, options))| fmt(key, value, options))
+            .map(|(key, value, options)| fmt(key, value, options))
             .map(|st| (header::SET_COOKIE, st.parse().unwrap()))
     }
 }
 
+/// Domains are case-insensitive, paths are not
+fn is_same_scope(a: &SetCookieOptions, b: &SetCookieOptions) -> bool {
+    let domain = |x: &SetCookieOptions| {
+        x.domain
+            .as_deref()
+            .map(|x| x.trim_start_matches('.').to_lowercase())
+    };
+
+    domain(a) == domain(b) && a.path == b.path
+}
+
 fn fmt(
     key: &str,
     value: &str,
     SetCookieOptions {
         domain,
+        expires,
         max_age,
         http_only,
         secure,
         path,
+        same_site,
+        partitioned,
+        priority,
     }: &SetCookieOptions,
 ) -> String {
     let mut base = format!("{}={}", key, value);
@@ -175,6 +489,10 @@ fn fmt(
         base = format!("{}; Domain={}", base, domain);
     }
 
+    if let Some(expires) = expires {
+        base = format!("{}; Expires={}", base, fmt_http_date(expires));
+    }
+
     if let Some(max_age) = max_age {
         base = format!("{}; Max-Age={}", base, max_age);
     }
@@ -187,10 +505,22 @@ fn fmt(
         base = format!("{}; HttpOnly", base);
     }
 
-    if *secure {
+    if *secure || *partitioned || *same_site == Some(SameSite::None) {
         base = format!("{}; Secure", base);
     }
 
+    if let Some(same_site) = same_site {
+        base = format!("{}; SameSite={}", base, same_site);
+    }
+
+    if *partitioned {
+        base = format!("{}; Partitioned", base);
+    }
+
+    if let Some(priority) = priority {
+        base = format!("{}; Priority={}", base, priority);
+    }
+
     base
 }
 
@@ -205,31 +535,18 @@ where
         let mut set_cookie = Self::new();
 
         for header_value in it {
-            let (options, key_value): (Vec<_>, Vec<_>) = header_value
-                .as_ref()
-                .split(';')
-                .map(|st| st.trim())
-                .partition(|st| SetCookieOptions::is_set_cookie_option(st));
-
-            // println!("options = {:?}", options);
-            // println!("key_value = {:?}", key_value);
+            let mut parts = header_value.as_ref().split(';');
 
-            let mut key_value = key_value.get(0).map(|st| st.split('='));
-
-            let key = key_value.as_mut().and_then(|st| st.next());
-            let value = key_value.as_mut().and_then(|st| st.next());
-
-            let (key, value) = match (key, value) {
-                (Some(key), Some(value)) => (key, value),
-                _ => continue,
+            let (key, value) = match parts.next().and_then(|st| st.split_once('=')) {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
             };
 
-            // println!("key = {}", key);
-            // println!("value = {}", value);
+            if key.is_empty() {
+                continue;
+            }
 
-            set_cookie
-                .inner
-                .insert(key.to_string(), (value.to_string(), options.into()));
+            set_cookie = set_cookie.set(key, value, parts.collect::<Vec<_>>().into());
         }
 
         set_cookie
@@ -240,7 +557,7 @@ where
 fn set_cookie_from_header_values() {
     let header_value = "key=value; Max-Age=12345; Domain=eeee.com; HttpOnly; Secure; Path=/abcd/e";
 
-    let it = vec![header_value];
+    let it = [header_value];
 
     let set_cookie = SetCookie::from(it.iter());
 
@@ -259,3 +576,157 @@ fn set_cookie_from_header_values() {
 
     assert_eq!(set_cookie, expected);
 }
+
+#[test]
+fn set_cookie_attributes() {
+    use chrono::TimeZone;
+
+    let expires = Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap();
+
+    let set_cookie = SetCookie::new().set(
+        "id",
+        "a3fWa",
+        SetCookieOptions::new()
+            .expires(expires)
+            .same_site(SameSite::None)
+            .partitioned(true)
+            .priority(Priority::High),
+    );
+
+    let (_, header_value) = set_cookie.iter().next().unwrap();
+
+    assert_eq!(
+        header_value,
+        "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; SameSite=None; Partitioned; Priority=High"
+    );
+
+    let parsed = SetCookie::from([header_value.to_str().unwrap()].iter());
+
+    assert_eq!(
+        parsed,
+        SetCookie::new().set(
+            "id",
+            "a3fWa",
+            SetCookieOptions::new()
+                .secure(true)
+                .expires(expires)
+                .same_site(SameSite::None)
+                .partitioned(true)
+                .priority(Priority::High),
+        )
+    );
+}
+
+#[test]
+fn set_cookie_keeps_case_and_order() {
+    let set_cookie = SetCookie::from_headers(
+        &[
+            "b=1; Domain=Example.COM; Path=/CaseSensitive",
+            "a=x=y; Path=/one",
+            "a=2; Path=/two",
+            "b=3; Domain=example.com; Path=/CaseSensitive",
+        ]
+        .into_iter()
+        .map(|x| (header::SET_COOKIE, HeaderValue::from_static(x)))
+        .collect(),
+    );
+
+    assert_eq!(set_cookie.len(), 3);
+    assert_eq!(
+        set_cookie
+            .iter()
+            .map(|(_, v)| v.to_str().unwrap().to_owned())
+            .collect::<Vec<_>>(),
+        [
+            "b=3; Domain=example.com; Path=/CaseSensitive",
+            "a=x=y; Path=/one",
+            "a=2; Path=/two",
+        ]
+    );
+    assert_eq!(
+        set_cookie
+            .get_all("a")
+            .map(|(v, options)| (v, options.path.as_deref().unwrap()))
+            .collect::<Vec<_>>(),
+        [("x=y", "/one"), ("2", "/two")]
+    );
+
+    let options = SetCookieOptions::from(vec!["Domain=Example.COM", "PATH=/CaseSensitive"]);
+
+    assert_eq!(options.domain.as_deref(), Some("Example.COM"));
+    assert_eq!(options.path.as_deref(), Some("/CaseSensitive"));
+}
+
+#[test]
+fn set_cookie_expire_and_typed_values() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Hint {
+        locale: String,
+    }
+
+    let hint = Hint {
+        locale: "ko-KR".to_owned(),
+    };
+
+    let set_cookie = SetCookie::new()
+        .set("n", "7", SetCookieOptions::new())
+        .set_json("hint", &hint, SetCookieOptions::new())
+        .unwrap()
+        .expire(
+            "session",
+            SetCookieOptions::new()
+                .domain("example.com")
+                .path("/auth")
+                .max_age(3600),
+        );
+
+    assert_eq!(set_cookie.get_as::<u8>("n"), Ok(7));
+    assert_eq!(set_cookie.get_json::<Hint>("hint").unwrap(), hint);
+
+    let header_values = set_cookie
+        .iter()
+        .map(|(_, v)| v.to_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        header_values[2],
+        "session=; Domain=example.com; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/auth"
+    );
+}
+
+#[test]
+fn set_cookie_expires_dates() {
+    for (st, timestamp) in [
+        ("Thu, 01 Jan 1970 00:00:01 GMT", 1),
+        ("Thu, 01-Jan-1970 00:00:01 GMT", 1),
+        ("Thursday, 01-Jan-70 00:00:01 GMT", 1),
+        ("Thu Jan  1 00:00:01 1970", 1),
+        ("1 january 1970 0:0:1", 1),
+        ("Sun, 06-Nov-1994 08:49:37 UTC", 784111777),
+        ("Wed, 09 Jun 2021 10:18:14 GMT", 1623233894),
+        ("Wed, 09-Jun-21 10:18:14 GMT", 1623233894),
+    ] {
+        assert_eq!(
+            parse_cookie_date(st).map(|x| x.timestamp()),
+            Some(timestamp),
+            "{st}"
+        );
+
+        let options = SetCookieOptions::from(vec![format!("Expires={st}").as_str()]);
+
+        assert_eq!(options.expires.map(|x| x.timestamp()), Some(timestamp));
+    }
+
+    for st in [
+        "",
+        "Thu, 01 Jan 1970",
+        "Thu, 32 Jan 1970 00:00:01 GMT",
+        "Thu, 30 Feb 1970 00:00:01 GMT",
+        "Thu, 01 Foo 1970 00:00:01 GMT",
+        "Thu, 01 Jan 1970 24:00:01 GMT",
+        "Thu, 01 Jan 1600 00:00:01 GMT",
+        "Thu, 001 Jan 1970 00:00:01 GMT",
+    ] {
+        assert_eq!(parse_cookie_date(st), None, "{st}");
+    }
+}
@@ -1,3 +1,9 @@
+mod path_pattern;
 mod path_variable;
+mod query;
+mod router;
 
+pub use path_pattern::*;
 pub use path_variable::*;
+pub use query::*;
+pub use router::*;
@@ -0,0 +1,353 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use super::{path_variable::parse_variable, PathVariable, PathVariableError};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PatternError {
+    #[error("Catch-all segment must be the last: {0}")]
+    CatchAllNotLast(String),
+    #[error("Empty variable name: {0}")]
+    EmptyName(String),
+    #[error("Duplicated variable name: {0}")]
+    DuplicateName(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Static(String),
+    /// `:id`, `:id?`
+    Variable {
+        name: String,
+        optional: bool,
+    },
+    /// `*rest`
+    CatchAll(String),
+}
+
+impl Segment {
+    /// Higher is more specific, the end of a pattern ranks between `:name` and `:name?`
+    fn rank(segment: Option<&Self>) -> u8 {
+        match segment {
+            Some(Segment::Static(_)) => 4,
+            Some(Segment::Variable {
+                optional: false, ..
+            }) => 3,
+            None => 2,
+            Some(Segment::Variable { optional: true, .. }) => 1,
+            Some(Segment::CatchAll(_)) => 0,
+        }
+    }
+}
+
+/// Compiled path pattern, `/users/:id/files/*rest`
+///
+/// - `:name` matches one segment
+/// - `:name?` matches one segment or nothing
+/// - `*name` matches the rest of the path, can be empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPattern {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let mut segments = Vec::new();
+        let mut names = Vec::new();
+
+        for (i, st) in split(pattern).enumerate() {
+            let segment = if let Some(name) = st.strip_prefix(':') {
+                match name.strip_suffix('?') {
+                    Some(name) => Segment::Variable {
+                        name: name.to_owned(),
+                        optional: true,
+                    },
+                    None => Segment::Variable {
+                        name: name.to_owned(),
+                        optional: false,
+                    },
+                }
+            } else if let Some(name) = st.strip_prefix('*') {
+                if i + 1 != split(pattern).count() {
+                    return Err(PatternError::CatchAllNotLast(pattern.to_owned()));
+                }
+
+                Segment::CatchAll(name.to_owned())
+            } else {
+                Segment::Static(st.to_owned())
+            };
+
+            if let Segment::Variable { name, .. } | Segment::CatchAll(name) = &segment {
+                if name.is_empty() {
+                    return Err(PatternError::EmptyName(pattern.to_owned()));
+                }
+
+                if names.contains(name) {
+                    return Err(PatternError::DuplicateName(name.to_owned()));
+                }
+
+                names.push(name.to_owned());
+            }
+
+            segments.push(segment);
+        }
+
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            segments,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.captures(path).is_some()
+    }
+
+    pub fn captures(&self, path: &str) -> Option<Captures> {
+        let parts = split(path).collect::<Vec<_>>();
+        let mut captures = Captures::default();
+
+        match_segments(&self.segments, &parts, &mut captures).then_some(captures)
+    }
+
+    /// Compares segment by segment, static > `:name` > end of the pattern > `:name?` > `*name`
+    pub fn specificity_cmp(&self, other: &Self) -> Ordering {
+        let len = self.segments.len().max(other.segments.len());
+
+        (0..len)
+            .map(|i| Segment::rank(self.segments.get(i)))
+            .cmp((0..len).map(|i| Segment::rank(other.segments.get(i))))
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = PatternError;
+
+    fn from_str(st: &str) -> Result<Self, Self::Err> {
+        Self::new(st)
+    }
+}
+
+/// Ignores leading and trailing `/`
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    let path = path.trim_matches('/');
+
+    path.split('/').filter(move |_| !path.is_empty())
+}
+
+fn match_segments(segments: &[Segment], parts: &[&str], captures: &mut Captures) -> bool {
+    let (segment, rest) = match segments.split_first() {
+        Some(x) => x,
+        None => return parts.is_empty(),
+    };
+
+    match segment {
+        Segment::Static(st) => {
+            parts.first() == Some(&st.as_str()) && match_segments(rest, &parts[1..], captures)
+        }
+        Segment::Variable { name, optional } => {
+            let len = captures.inner.len();
+
+            if let Some(part) = parts.first().filter(|x| !x.is_empty()) {
+                captures.inner.push((name.clone(), Some(part.to_string())));
+
+                if match_segments(rest, &parts[1..], captures) {
+                    return true;
+                }
+
+                captures.inner.truncate(len);
+            }
+
+            if !optional {
+                return false;
+            }
+
+            // kept as skipped so that positions of the later variables don't shift
+            captures.inner.push((name.clone(), None));
+
+            if match_segments(rest, parts, captures) {
+                return true;
+            }
+
+            captures.inner.truncate(len);
+
+            false
+        }
+        Segment::CatchAll(name) => {
+            captures.inner.push((name.clone(), Some(parts.join("/"))));
+            true
+        }
+    }
+}
+
+/// Variables captured by [`PathPattern`], in order of the pattern
+///
+/// Segments are kept raw, [`Captures::get`] and [`Captures::try_get`] percent-decode them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Captures {
+    /// `None` for a skipped `:name?`
+    inner: Vec<(String, Option<String>)>,
+}
+
+impl Captures {
+    pub fn get<T>(&self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.try_get(name).ok()
+    }
+
+    pub fn try_get<T>(&self, name: &str) -> Result<T, PathVariableError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let raw = self
+            .get_str(name)
+            .ok_or_else(|| PathVariableError::Missing(name.to_owned()))?;
+
+        parse_variable(name, raw)
+    }
+
+    /// Raw segment
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.inner
+            .iter()
+            .find(|(k, _)| k == name)
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    /// Skipped `:name?` are not counted
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Skipped `:name?` are not included
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.inner
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str(), v.as_deref()?)))
+    }
+}
+
+/// Skipped `:name?` are yielded as missing
+impl From<Captures> for PathVariable {
+    fn from(captures: Captures) -> Self {
+        PathVariable::from_named(captures.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathPattern, PathVariableError, PatternError};
+    use crate::PathVariable;
+
+    #[test]
+    fn test_path_pattern() {
+        let pattern = PathPattern::new("/users/:id/files/*rest").unwrap();
+
+        let captures = pattern.captures("/users/12/files/a/b.txt").unwrap();
+
+        assert_eq!(captures.get::<u64>("id"), Some(12));
+        assert_eq!(captures.get_str("rest"), Some("a/b.txt"));
+
+        assert_eq!(
+            pattern.captures("/users/12/files").unwrap().get_str("rest"),
+            Some("")
+        );
+        assert!(!pattern.is_match("/users/12"));
+        assert!(!pattern.is_match("/users"));
+        assert!(!pattern.is_match("/groups/12/files/a"));
+
+        let mut variables = PathVariable::from(captures);
+
+        assert_eq!(variables.next_variable::<u64>(), Some(12));
+        assert_eq!(
+            variables.next_variable::<String>(),
+            Some("a/b.txt".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_path_pattern_optional() {
+        let pattern = PathPattern::new("/posts/:year?/:slug").unwrap();
+
+        let captures = pattern.captures("/posts/2024/hello").unwrap();
+
+        assert_eq!(captures.get::<u32>("year"), Some(2024));
+        assert_eq!(captures.get_str("slug"), Some("hello"));
+
+        let captures = pattern.captures("/posts/hello/").unwrap();
+
+        assert_eq!(captures.get_str("year"), None);
+        assert_eq!(captures.get_str("slug"), Some("hello"));
+
+        assert!(!pattern.is_match("/posts"));
+    }
+
+    #[test]
+    fn test_path_variable_skipped_optional() {
+        let pattern = PathPattern::new("/a/:x?/:y").unwrap();
+
+        let captures = pattern.captures("/a/5").unwrap();
+
+        assert_eq!(captures.get_str("x"), None);
+        assert_eq!(captures.get_str("y"), Some("5"));
+        assert_eq!(captures.iter().collect::<Vec<_>>(), [("y", "5")]);
+        assert_eq!(captures.len(), 1);
+
+        let mut variables = PathVariable::from(captures);
+
+        assert_eq!(
+            variables.try_next_variable::<u32>(),
+            Err(PathVariableError::Missing("x".to_owned()))
+        );
+        assert_eq!(variables.next_variable::<u32>(), Some(5));
+
+        let mut variables = PathVariable::from(pattern.captures("/a/4/5").unwrap());
+
+        assert_eq!(variables.next_variable::<u32>(), Some(4));
+        assert_eq!(variables.next_variable::<u32>(), Some(5));
+    }
+
+    #[test]
+    fn test_captures_decode() {
+        let pattern = PathPattern::new("/files/:id/:name").unwrap();
+        let captures = pattern.captures("/files/x1/my%20doc").unwrap();
+
+        assert_eq!(captures.get_str("name"), Some("my%20doc"));
+        assert_eq!(captures.get::<String>("name"), Some("my doc".to_owned()));
+        assert!(matches!(
+            captures.try_get::<u64>("id"),
+            Err(PathVariableError::Unparsable { name, .. }) if name == "id"
+        ));
+        assert_eq!(
+            captures.try_get::<u64>("version"),
+            Err(PathVariableError::Missing("version".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_path_pattern_error() {
+        assert_eq!(
+            PathPattern::new("/a/*rest/b"),
+            Err(PatternError::CatchAllNotLast("/a/*rest/b".to_owned()))
+        );
+        assert_eq!(
+            PathPattern::new("/a/:"),
+            Err(PatternError::EmptyName("/a/:".to_owned()))
+        );
+        assert_eq!(
+            PathPattern::new("/a/:id/:id"),
+            Err(PatternError::DuplicateName("id".to_owned()))
+        );
+    }
+}
@@ -1,33 +1,82 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use percent_encoding::percent_decode_str;
 
 pub fn is_path_variable(st: &str) -> bool {
     st.starts_with(':')
 }
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PathVariableError {
+    #[error("Missing path variable: {0}")]
+    Missing(String),
+    #[error("Path variable {0} is not percent-encoded UTF-8")]
+    Undecodable(String),
+    #[error("Path variable {name} is unparsable: {message}")]
+    Unparsable { name: String, message: String },
+}
+
+impl PathVariableError {
+    /// Name of the variable, or its position if it is unnamed
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Missing(name) | Self::Undecodable(name) | Self::Unparsable { name, .. } => name,
+        }
+    }
+}
+
+/// Percent-decodes then parses a path segment
+pub(super) fn parse_variable<T>(name: &str, raw: &str) -> Result<T, PathVariableError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let decoded = percent_decode_str(raw)
+        .decode_utf8()
+        .map_err(|_| PathVariableError::Undecodable(name.to_owned()))?;
+
+    decoded
+        .parse()
+        .map_err(|e: T::Err| PathVariableError::Unparsable {
+            name: name.to_owned(),
+            message: e.to_string(),
+        })
+}
+
+/// Variables of a request path in order, segments are percent-decoded
 pub struct PathVariable {
-    it: Box<dyn Iterator<Item = String> + Send>,
+    /// (name, raw segment), the segment is `None` if the request path is shorter than the pattern
+    it: std::vec::IntoIter<(String, Option<String>)>,
+    position: usize,
 }
 
 impl PathVariable {
     pub fn new(request_path: &str, defined_path: &str) -> Self {
-        let variable_indexes = defined_path
+        let request_path = request_path.split('/').collect::<Vec<_>>();
+
+        let variables = defined_path
             .split('/')
             .enumerate()
             .filter(|(_, e)| is_path_variable(e))
-            .map(|(i, _)| i);
-
-        let mut splitted_request_path = request_path.split('/').enumerate();
-
-        let path_variables = variable_indexes
-            .map(|i| {
-                splitted_request_path
-                    .find(|(j, _)| &i == j)
-                    .map(|(_, var)| var.into())
-                    .unwrap()
+            .map(|(i, e)| {
+                (
+                    e.trim_start_matches(':').to_owned(),
+                    request_path.get(i).map(|x| x.to_string()),
+                )
             })
             .collect::<Vec<_>>();
 
-        path_variables.into()
+        Self::from_named(variables)
+    }
+
+    pub(super) fn from_named(variables: Vec<(String, Option<String>)>) -> Self {
+        Self {
+            it: variables.into_iter(),
+            position: 0,
+        }
     }
 
     pub fn next_variable<T>(&mut self) -> Option<T>
@@ -35,15 +84,53 @@ impl PathVariable {
         T: FromStr,
         T::Err: Debug,
     {
-        self.it.next().and_then(|var| var.parse::<T>().ok())
+        let raw = self.next_raw()?.1?;
+        let decoded = percent_decode_str(&raw).decode_utf8().ok()?;
+
+        decoded.parse().ok()
+    }
+
+    /// Like [`PathVariable::next_variable`] but tells why the variable is unavailable
+    pub fn try_next_variable<T>(&mut self) -> Result<T, PathVariableError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.next_raw() {
+            Some((name, Some(raw))) => parse_variable(&name, &raw),
+            Some((name, None)) => Err(PathVariableError::Missing(name)),
+            None => Err(PathVariableError::Missing(self.position.to_string())),
+        }
+    }
+
+    fn next_raw(&mut self) -> Option<(String, Option<String>)> {
+        self.position += 1;
+
+        self.it.next()
     }
 }
 
+/// Unnamed variables, errors name them by position starting from 1
 impl From<Vec<String>> for PathVariable {
     fn from(variables: Vec<String>) -> Self {
-        Self {
-            it: Box::new(variables.into_iter()),
-        }
+        Self::from_named(
+            variables
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| ((i + 1).to_string(), Some(x)))
+                .collect(),
+        )
+    }
+}
+
+impl From<Vec<(String, String)>> for PathVariable {
+    fn from(variables: Vec<(String, String)>) -> Self {
+        Self::from_named(
+            variables
+                .into_iter()
+                .map(|(name, x)| (name, Some(x)))
+                .collect(),
+        )
     }
 }
 
@@ -52,3 +139,45 @@ impl From<(&str, &str)> for PathVariable {
         PathVariable::new(req_path, pattern)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{PathVariable, PathVariableError};
+
+    #[test]
+    fn test_path_variable() {
+        let mut variables = PathVariable::new("/files/my%20doc/12", "/files/:name/:version");
+
+        assert_eq!(
+            variables.next_variable::<String>(),
+            Some("my doc".to_owned())
+        );
+        assert_eq!(variables.next_variable::<u32>(), Some(12));
+        assert_eq!(variables.next_variable::<u32>(), None);
+    }
+
+    #[test]
+    fn test_path_variable_error() {
+        let mut variables = PathVariable::new("/users/abc/%FF", "/users/:id/:name/:tab");
+
+        assert_eq!(
+            variables.try_next_variable::<u64>(),
+            Err(PathVariableError::Unparsable {
+                name: "id".to_owned(),
+                message: "invalid digit found in string".to_owned()
+            })
+        );
+        assert_eq!(
+            variables.try_next_variable::<String>(),
+            Err(PathVariableError::Undecodable("name".to_owned()))
+        );
+        assert_eq!(
+            variables.try_next_variable::<String>(),
+            Err(PathVariableError::Missing("tab".to_owned()))
+        );
+        assert_eq!(
+            variables.try_next_variable::<String>().unwrap_err().name(),
+            "4"
+        );
+    }
+}
@@ -0,0 +1,524 @@
+use std::fmt::Display;
+
+use percent_encoding::percent_decode_str;
+use serde::de::{
+    self, value::StringDeserializer, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor,
+};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QueryError {
+    #[error("Missing query parameter: {0}")]
+    Missing(String),
+    #[error("Invalid query parameter {key}: {message}")]
+    Invalid { key: String, message: String },
+    #[error("Invalid query: {0}")]
+    Custom(String),
+}
+
+impl de::Error for QueryError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::Missing(field.to_owned())
+    }
+}
+
+impl QueryError {
+    /// Prefixes the key of the error with `key`, `c[d]` -> `b[c][d]`
+    fn at(self, key: &str) -> Self {
+        let join = |inner: &str| match inner.split_once('[') {
+            Some((name, rest)) => format!("{key}[{name}][{rest}"),
+            None => format!("{key}[{inner}]"),
+        };
+
+        match self {
+            Self::Custom(message) => Self::Invalid {
+                key: key.to_owned(),
+                message,
+            },
+            Self::Invalid {
+                key: inner,
+                message,
+            } => Self::Invalid {
+                key: join(&inner),
+                message,
+            },
+            Self::Missing(inner) => Self::Missing(join(&inner)),
+        }
+    }
+}
+
+/// Typed query string, `?id=1&tag=a&tag=b&filter[name]=syr`
+///
+/// Repeated keys and `tag[]=a` are deserialized as `Vec`, `filter[name]=syr` as nested struct or map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<T>(pub T);
+
+impl<T> Query<T>
+where
+    T: DeserializeOwned,
+{
+    pub fn from_query(query: &str) -> Result<Self, QueryError> {
+        T::deserialize(parse(query)?).map(Query)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait::async_trait]
+impl<'a, T> crate::FromRequest<'a> for Query<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Parameter = ();
+    type Error = QueryError;
+
+    async fn from_request(
+        _: Self::Parameter,
+        request: &'a mut http::Request<hyper::Body>,
+    ) -> Result<Self, Self::Error> {
+        Self::from_query(request.uri().query().unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Leaf(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+fn decode(st: &str) -> String {
+    percent_decode_str(&st.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Brackets nested deeper are kept in the last key as they are, `a[b][c][d][e][f][g][h]` ->
+/// `a`, `[b, c, d, e, f, "[g][h]"]`
+const MAX_DEPTH: usize = 5;
+
+fn parse(query: &str) -> Result<Node, QueryError> {
+    let mut root = Vec::new();
+
+    for pair in query.split('&').filter(|x| !x.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (key, value) = (decode(key), decode(value));
+
+        // a[b][] -> a, [b, ""]
+        let (name, path) = match key.split_once('[') {
+            Some((name, rest)) if rest.ends_with(']') => {
+                let inner = &rest[..rest.len() - 1];
+
+                let path = match inner.match_indices("][").nth(MAX_DEPTH - 1) {
+                    Some((i, _)) => inner[..i].split("][").chain([&rest[i + 1..]]).collect(),
+                    None => inner.split("][").collect::<Vec<_>>(),
+                };
+
+                (name, path)
+            }
+            _ => (key.as_str(), Vec::new()),
+        };
+
+        insert(&mut root, name, &path, value).map_err(|_| QueryError::Invalid {
+            key: key.clone(),
+            message: "conflicts with another parameter".to_owned(),
+        })?;
+    }
+
+    Ok(Node::Map(root))
+}
+
+/// Fails on conflicting keys such as `a=1&a[b]=2`
+fn insert(
+    map: &mut Vec<(String, Node)>,
+    key: &str,
+    path: &[&str],
+    value: String,
+) -> Result<(), ()> {
+    let pos = match map.iter().position(|(k, _)| k == key) {
+        Some(pos) => pos,
+        None => {
+            let node = match path.first() {
+                None => {
+                    map.push((key.to_owned(), Node::Leaf(value)));
+                    return Ok(());
+                }
+                Some(&"") => Node::Seq(Vec::new()),
+                Some(_) => Node::Map(Vec::new()),
+            };
+
+            map.push((key.to_owned(), node));
+            map.len() - 1
+        }
+    };
+
+    let node = &mut map[pos].1;
+
+    match (path.first(), node) {
+        // repeated key
+        (None | Some(&""), node @ Node::Leaf(_)) => {
+            let prev = std::mem::replace(node, Node::Seq(Vec::new()));
+
+            match node {
+                Node::Seq(xs) => {
+                    xs.push(prev);
+                    push(xs, path, value)
+                }
+                _ => Ok(()),
+            }
+        }
+        (None | Some(&""), Node::Seq(xs)) => push(xs, path, value),
+        (Some(key), Node::Map(xs)) if !key.is_empty() => insert(xs, key, &path[1..], value),
+        // conflicting keys such as `a=1&a[b]=2`
+        _ => Err(()),
+    }
+}
+
+fn push(xs: &mut Vec<Node>, path: &[&str], value: String) -> Result<(), ()> {
+    match path.get(1) {
+        Some(key) => {
+            let mut map = Vec::new();
+            insert(&mut map, key, &path[2..], value)?;
+            xs.push(Node::Map(map));
+        }
+        None => xs.push(Node::Leaf(value)),
+    }
+
+    Ok(())
+}
+
+impl Node {
+    fn into_leaf(self) -> Result<String, QueryError> {
+        match self {
+            Node::Leaf(x) => Ok(x),
+            Node::Seq(mut xs) if xs.len() == 1 => xs.remove(0).into_leaf(),
+            Node::Seq(_) => Err(de::Error::custom("expected a single value")),
+            Node::Map(_) => Err(de::Error::custom("expected a value")),
+        }
+    }
+
+    fn into_seq(self) -> Vec<Node> {
+        match self {
+            Node::Seq(xs) => xs,
+            // a[1]=x&a[0]=y
+            Node::Map(xs) if xs.iter().all(|(k, _)| k.parse::<usize>().is_ok()) => {
+                let mut xs = xs
+                    .into_iter()
+                    .map(|(k, v)| (k.parse::<usize>().unwrap_or_default(), v))
+                    .collect::<Vec<_>>();
+                xs.sort_by_key(|(i, _)| *i);
+                xs.into_iter().map(|(_, v)| v).collect()
+            }
+            node => vec![node],
+        }
+    }
+}
+
+macro_rules! deserialize_parse {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let st = self.into_leaf()?;
+
+                match st.parse() {
+                    Ok(x) => visitor.$visit(x),
+                    Err(_) => Err(de::Error::custom(format!("invalid value {st:?}"))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Node::Leaf(x) => visitor.visit_string(x),
+            Node::Seq(xs) => visitor.visit_seq(SeqAccess::new(xs)),
+            Node::Map(xs) => visitor.visit_map(MapAccess::new(xs)),
+        }
+    }
+
+    deserialize_parse! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.into_leaf()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.into_leaf()?)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.into_leaf()?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            // `?page=`
+            Node::Leaf(x) if x.is_empty() => visitor.visit_none(),
+            node => visitor.visit_some(node),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(SeqAccess::new(self.into_seq()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Node::Map(xs) => visitor.visit_map(MapAccess::new(xs)),
+            _ => Err(de::Error::custom("expected nested parameters")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let st: StringDeserializer<QueryError> = self.into_leaf()?.into_deserializer();
+
+        visitor.visit_enum(st)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct
+    }
+}
+
+struct SeqAccess {
+    it: std::iter::Enumerate<std::vec::IntoIter<Node>>,
+}
+
+impl SeqAccess {
+    fn new(xs: Vec<Node>) -> Self {
+        Self {
+            it: xs.into_iter().enumerate(),
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = QueryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.it.next() {
+            Some((i, node)) => seed
+                .deserialize(node)
+                .map(Some)
+                .map_err(|err| err.at(&i.to_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapAccess {
+    it: std::vec::IntoIter<(String, Node)>,
+    value: Option<(String, Node)>,
+}
+
+impl MapAccess {
+    fn new(xs: Vec<(String, Node)>) -> Self {
+        Self {
+            it: xs.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = QueryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.it.next() {
+            Some((key, node)) => {
+                let r = seed.deserialize(key.clone().into_deserializer())?;
+                self.value = Some((key, node));
+                Ok(Some(r))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, node) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+
+        seed.deserialize(node).map_err(|err| err.at(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::{Query, QueryError};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Filter {
+        name: String,
+        age: Option<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Search {
+        page: u32,
+        tag: Vec<String>,
+        id: Vec<u64>,
+        filter: Filter,
+        order: Option<Order>,
+        extra: HashMap<String, String>,
+    }
+
+    #[test]
+    fn test_query() {
+        let Query(search) = Query::<Search>::from_query(
+            "page=2&tag=a+b&tag=c%26d&id[]=3&filter[name]=%EC%8B%9C&order=desc&extra%5Bx%5D=y",
+        )
+        .unwrap();
+
+        assert_eq!(
+            search,
+            Search {
+                page: 2,
+                tag: vec!["a b".to_owned(), "c&d".to_owned()],
+                id: vec![3],
+                filter: Filter {
+                    name: "시".to_owned(),
+                    age: None,
+                },
+                order: Some(Order::Desc),
+                extra: HashMap::from([("x".to_owned(), "y".to_owned())]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_error() {
+        let r = Query::<Search>::from_query("tag=a&id=1&filter[name]=a&extra[x]=y");
+
+        assert_eq!(r, Err(QueryError::Missing("page".to_owned())));
+
+        let r = Query::<Search>::from_query("page=1&tag=a&id=1&extra[x]=y&filter[age]=1");
+
+        assert_eq!(r, Err(QueryError::Missing("filter[name]".to_owned())));
+
+        let r = Query::<Search>::from_query("page=1&tag=a&id=1&id=x&filter[name]=a&extra[x]=y");
+
+        assert!(matches!(r, Err(QueryError::Invalid { key, .. }) if key == "id[1]"));
+
+        let r = Query::<Search>::from_query(
+            "page=1&tag=a&id=1&filter[name]=a&filter[age]=-1&extra[x]=y",
+        );
+
+        assert!(matches!(r, Err(QueryError::Invalid { key, .. }) if key == "filter[age]"));
+
+        for query in ["a=1&a[b]=2", "a[b]=1&a=2", "a[]=1&a[b]=2", "a[b]=1&a[]=2"] {
+            let r = Query::<HashMap<String, serde_json::Value>>::from_query(query);
+
+            assert!(
+                matches!(&r, Err(QueryError::Invalid { key, .. }) if key.starts_with("a")),
+                "{query}: {r:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_depth() {
+        let Query(value) =
+            Query::<serde_json::Value>::from_query("a[b][c][d][e][f][g][h]=1").unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "a": { "b": { "c": { "d": { "e": { "f": { "[g][h]": "1" } } } } } },
+            })
+        );
+
+        // would overflow the stack if every bracket were nested
+        let query = format!("a{}=1", "[a]".repeat(20000));
+
+        assert!(Query::<HashMap<String, String>>::from_query(&query).is_err());
+        assert!(Query::<serde_json::Value>::from_query(&query).is_ok());
+    }
+}
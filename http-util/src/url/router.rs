@@ -0,0 +1,112 @@
+use super::{Captures, PathPattern, PatternError};
+
+/// Routing table which picks the most specific matched pattern
+///
+/// ```no_run
+/// # use http_util::Router;
+/// # enum Route {
+/// #     Me,
+/// #     User,
+/// #     File,
+/// # }
+/// # fn main() -> Result<(), http_util::PatternError> {
+/// let router = Router::new()
+///     .route("/users/me", Route::Me)?
+///     .route("/users/:id", Route::User)?
+///     .route("/files/*rest", Route::File)?;
+///
+/// let (route, captures) = router.find("/users/12").unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Router<H> {
+    routes: Vec<(PathPattern, H)>,
+}
+
+impl<H> Router<H> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    pub fn route(mut self, pattern: &str, handler: H) -> Result<Self, PatternError> {
+        self.insert(pattern, handler)?;
+
+        Ok(self)
+    }
+
+    pub fn insert(&mut self, pattern: &str, handler: H) -> Result<(), PatternError> {
+        self.routes.push((PathPattern::new(pattern)?, handler));
+
+        Ok(())
+    }
+
+    /// The first inserted one wins if several patterns are equally specific
+    pub fn find(&self, path: &str) -> Option<(&H, Captures)> {
+        self.routes
+            .iter()
+            .filter_map(|(pattern, handler)| {
+                pattern
+                    .captures(path)
+                    .map(|captures| (pattern, handler, captures))
+            })
+            .reduce(|best, x| {
+                if x.0.specificity_cmp(best.0).is_gt() {
+                    x
+                } else {
+                    best
+                }
+            })
+            .map(|(_, handler, captures)| (handler, captures))
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+impl<H> Default for Router<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Router;
+
+    #[test]
+    fn test_router() {
+        let router = Router::new()
+            .route("/files/*rest", "files")
+            .unwrap()
+            .route("/users/:id", "user")
+            .unwrap()
+            .route("/users/me", "me")
+            .unwrap()
+            .route("/users/:id/:tab?", "user_tab")
+            .unwrap()
+            .route("/files/:name", "file")
+            .unwrap();
+
+        let (handler, captures) = router.find("/users/12").unwrap();
+
+        assert_eq!(*handler, "user");
+        assert_eq!(captures.get::<u64>("id"), Some(12));
+
+        assert_eq!(*router.find("/users/me").unwrap().0, "me");
+        assert_eq!(*router.find("/users/12/posts").unwrap().0, "user_tab");
+        assert_eq!(*router.find("/files/a.txt").unwrap().0, "file");
+
+        let (handler, captures) = router.find("/files/a/b.txt").unwrap();
+
+        assert_eq!(*handler, "files");
+        assert_eq!(captures.get_str("rest"), Some("a/b.txt"));
+
+        assert!(router.find("/groups/1").is_none());
+    }
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "derive")]
+
+use http::{header, Request, StatusCode};
+use http_util::{FromRequest, PathVariable, PathVariableError, ToPayload};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Paging {
+    page: u32,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct NewFile {
+    name: String,
+}
+
+#[derive(Debug, FromRequest)]
+struct UploadFile {
+    #[path_variable]
+    user_id: u64,
+    #[path_variable]
+    folder: String,
+    #[query]
+    paging: Paging,
+    #[body]
+    file: NewFile,
+    #[cookie("access_token")]
+    access_token: String,
+    #[header("x-request-id")]
+    request_id: Option<u32>,
+}
+
+#[derive(Debug, FromRequest)]
+struct Whoami {
+    #[header("x-user-id")]
+    user_id: u64,
+}
+
+#[tokio::test]
+async fn test_derive_from_request() {
+    let mut request = Request::post("/users/12/my%20docs?page=3")
+        .header(header::COOKIE, "access_token=abcd; theme=dark")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(r#"{"name": "a.txt"}"#.into())
+        .unwrap();
+
+    let variables = PathVariable::new("/users/12/my%20docs", "/users/:user_id/:folder");
+
+    let upload: UploadFile = request.to_payload(variables).await.unwrap();
+
+    assert_eq!(upload.user_id, 12);
+    assert_eq!(upload.folder, "my docs");
+    assert_eq!(upload.paging, Paging { page: 3 });
+    assert_eq!(upload.file.name, "a.txt");
+    assert_eq!(upload.access_token, "abcd");
+    assert_eq!(upload.request_id, None);
+}
+
+#[tokio::test]
+async fn test_derive_from_request_error() {
+    let mut request = Request::post("/users/12?page=3")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(r#"{"name": "a.txt"}"#.into())
+        .unwrap();
+
+    let variables = PathVariable::new("/users/12", "/users/:user_id/:folder");
+
+    let err = UploadFile::from_request(variables, &mut request)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        &err,
+        UploadFileError::Path(PathVariableError::Missing(name)) if name == "folder"
+    ));
+    assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+
+    let mut request = Request::get("/whoami")
+        .header("x-user-id", "7")
+        .body(Default::default())
+        .unwrap();
+
+    assert_eq!(
+        Whoami::from_request((), &mut request)
+            .await
+            .unwrap()
+            .user_id,
+        7
+    );
+
+    let mut request = Request::get("/whoami")
+        .header("x-user-id", "me")
+        .body(Default::default())
+        .unwrap();
+
+    let err = Whoami::from_request((), &mut request).await.unwrap_err();
+
+    assert!(matches!(
+        err,
+        WhoamiError::InvalidHeader {
+            name: "x-user-id",
+            ..
+        }
+    ));
+    assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+    let mut request = Request::get("/whoami").body(Default::default()).unwrap();
+
+    assert_eq!(
+        Whoami::from_request((), &mut request)
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Missing header: x-user-id"
+    );
+}
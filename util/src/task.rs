@@ -22,7 +22,7 @@ impl<T> Eq for TaskItem<T> {}
 
 impl<T> PartialOrd for TaskItem<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.0.partial_cmp(&self.0)
+        Some(self.cmp(other))
     }
 }
 